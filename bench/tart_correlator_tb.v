@@ -67,6 +67,24 @@ module tart_correlator_tb;
   initial #6000 $finish;
 
 
+`ifdef __golden
+  // -- Golden input vectors -- //
+
+  // Generated using 'cargo run -- --golden <DIR>', in 'bench/test_vectors'.
+  // Note: only the '{Q, I}' inputs are replayed, as the correlator taps are
+  //   not yet parameters of 'tart_correlator', so the output order is not
+  //   known, and the outputs are not checked.
+  localparam integer GLD_INPUT = COUNT << 1;
+
+  reg [2*WIDTH-1:0] gld_input[GLD_INPUT];
+  integer gld_index = 0;
+
+  initial begin
+    $readmemh("tart_correlator_tb_input.hex", gld_input);
+  end
+`endif
+
+
   // -- Generate fake data -- //
 
   reg sig_valid;
@@ -117,7 +135,12 @@ module tart_correlator_tb;
       sig_last <= clast && !sig_done;
 
       if (go_w) begin
+`ifdef __golden
+        {sig_qdata, sig_idata} <= gld_input[gld_index];
+        gld_index <= gld_index + 1;
+`else
         {sig_idata, sig_qdata} <= $urandom;
+`endif
       end else begin
         {sig_idata, sig_qdata} <= {WIDTH{2'bxx}};
       end
@@ -0,0 +1,91 @@
+/* Conversions between ADC sample values, and their sign/magnitude codes, for
+ * the 1-bit and 2-bit data from the MAX2769.
+ */
+
+/**
+ * Convert an ADC value to its sign/magnitude code. See Table 16 in the MAX2769
+ * data sheet. The sign is the MSB, and for 2-bit data, the magnitude bit is
+ * high for the values +/-3 (or larger), so that the levels {-3, -1, 1, 3} are
+ * the values of the codes {0b11, 0b10, 0b00, 0b01}.
+ */
+pub fn to_sign_magnitude(a: i32, nbits: u8) -> i32 {
+    match nbits {
+        1 => {
+            if a < 0 {
+                1
+            } else {
+                0
+            }
+        }
+        2 => {
+            if a <= -3 {
+                0b11
+            } else if a < 0 {
+                0b10
+            } else if a < 3 {
+                0b00
+            } else {
+                0b01
+            }
+        }
+        _ => {
+            println!("Only work with 1 or two bit sign magnitude data");
+            0
+        }
+    }
+}
+
+/**
+ * The ADC value represented by each sign/magnitude code. This is the inverse
+ * of 'to_sign_magnitude', for the ADC levels, where 1-bit codes are the values
+ * +/-1, and 2-bit codes are the values {-3, -1, 1, 3}.
+ */
+pub fn from_sign_magnitude(code: i32, nbits: u8) -> i32 {
+    match nbits {
+        1 => {
+            if code & 1 == 1 {
+                -1
+            } else {
+                1
+            }
+        }
+        2 => {
+            let mag = if code & 0b01 == 0b01 { 3 } else { 1 };
+            if code & 0b10 == 0b10 {
+                -mag
+            } else {
+                mag
+            }
+        }
+        _ => {
+            println!("Only work with 1 or two bit sign magnitude data");
+            0
+        }
+    }
+}
+
+/**
+ * Largest ADC value magnitude, for the given number of ADC bits.
+ */
+pub fn max_level(nbits: u8) -> i32 {
+    if nbits == 2 {
+        3
+    } else {
+        1
+    }
+}
+
+/**
+ * Number of bits required to store all values within '[-max, max]', using
+ * two's-complement.
+ */
+pub fn signed_bits(max: i32) -> usize {
+    (i32::BITS - max.unsigned_abs().leading_zeros()) as usize + 1
+}
+
+/**
+ * Number of bits required to store all values within '[0, max]'.
+ */
+pub fn unsigned_bits(max: i32) -> usize {
+    ((i32::BITS - max.unsigned_abs().leading_zeros()) as usize).max(1)
+}
@@ -0,0 +1,231 @@
+use clap::Parser;
+use std::path::Path;
+use test_vectors::golden;
+use test_vectors::vcd::{compare, read_memh, split_words, Edge, Mismatch, Sample, Vcd};
+
+/// Samples the named signals of a simulation (VCD) on each clock-edge, and
+/// compares the valid outputs against the reference model, reporting the
+/// first mismatching cycle.
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// VCD file, written by the testbench
+    vcd: String,
+
+    /// Testbench (sigsource_tb, correlate_tb, visfinal_tb, or
+    /// tart_correlator_tb), which sets the signals, and computes the expected
+    /// values from its input vectors (except for tart_correlator_tb)
+    #[arg(short, long)]
+    testbench: Option<String>,
+
+    /// Directory of the golden vectors (from 'test_vectors --golden')
+    #[arg(short, long, default_value_t = String::from("."))]
+    golden: String,
+
+    /// Clock signal [default: 'clock', or that of the testbench]
+    #[arg(long)]
+    clock: Option<String>,
+
+    /// Sample on the falling, instead of the rising, clock-edges
+    #[arg(long, default_value_t = false)]
+    falling: bool,
+
+    /// Signal that marks the valid outputs (every cycle, if not given)
+    #[arg(long)]
+    valid: Option<String>,
+
+    /// Signals to compare, with the first in the MSBs of the expected words
+    #[arg(short, long)]
+    signal: Vec<String>,
+
+    /// Additional signals to display (e.g., 'bus_last_o'), but not compare
+    #[arg(long)]
+    show: Vec<String>,
+
+    /// Expected values, as a '$readmemh' file, of the packed signals
+    #[arg(short, long)]
+    expected: Option<String>,
+
+    /// Number of cycles of context to show, around a mismatch
+    #[arg(short, long, default_value_t = 4)]
+    context: usize,
+}
+
+/* Testbench signals: the clock, the valid strobe, the compared outputs, and
+ * any additional signals to display.
+ */
+type Preset = (
+    &'static str,
+    &'static str,
+    Vec<&'static str>,
+    Vec<&'static str>,
+);
+
+fn preset(name: &str) -> Option<Preset> {
+    match name {
+        "sigsource_tb" => Some(("clock", "mux_valid", vec!["ai", "aq", "bi", "bq"], vec![])),
+        "correlate_tb" => Some(("clock", "cor_valid", vec!["cor_rdata", "cor_idata"], vec![])),
+        "visfinal_tb" => Some(("clock", "vld_w", vec!["dat_w"], vec![])),
+        "tart_correlator_tb" => Some((
+            "bus_clock",
+            "bus_valid_o",
+            vec!["bus_revis_o", "bus_imvis_o"],
+            vec!["bus_last_o"],
+        )),
+        _ => None,
+    }
+}
+
+fn print_row(sample: &Sample, names: &[String], expected: Option<&Vec<u64>>, mark: bool) {
+    let values: Vec<String> = names
+        .iter()
+        .zip(sample.values.iter())
+        .map(|(n, v)| format!("{}={}", n, v))
+        .collect();
+    let expected = match expected {
+        Some(x) => format!(
+            "  (expected: {})",
+            x.iter()
+                .map(|v| format!("{:x}", v))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        None => String::new(),
+    };
+    println!(
+        "{} cycle {:6} @{:10}: {}{}",
+        if mark { ">>" } else { "  " },
+        sample.cycle,
+        sample.time,
+        values.join(", "),
+        expected
+    );
+}
+
+/* Show the cycles around the mismatch, for all sampled signals.
+ */
+fn print_context(
+    mismatch: &Mismatch,
+    samples: &[Sample],
+    valids: &[&Sample],
+    names: &[String],
+    context: usize,
+) {
+    let cycle = match &mismatch.sample {
+        Some(sample) => sample.cycle,
+        None => valids.last().map(|s| s.cycle).unwrap_or(0),
+    };
+    let lo = cycle.saturating_sub(context);
+    let hi = (cycle + context + 1).min(samples.len());
+    for sample in samples[lo..hi].iter() {
+        let mark = mismatch
+            .sample
+            .as_ref()
+            .is_some_and(|s| s.cycle == sample.cycle);
+        let expected = if mark { Some(&mismatch.expected) } else { None };
+        print_row(sample, names, expected, mark);
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    let vcd = Vcd::parse(&std::fs::read_to_string(&args.vcd)?)?;
+
+    let (mut clock, mut valid, mut signals, mut show) = (
+        args.clock.clone(),
+        args.valid.clone(),
+        args.signal.clone(),
+        args.show.clone(),
+    );
+    let mut expected_words = None;
+
+    if let Some(tb) = &args.testbench {
+        let (c, v, s, d) = preset(tb).ok_or(format!("Unknown testbench: {}", tb))?;
+        clock = clock.or(Some(c.to_string()));
+        valid = valid.or(Some(v.to_string()));
+        if signals.is_empty() {
+            signals = s.iter().map(|x| x.to_string()).collect();
+        }
+        if show.is_empty() {
+            show = d.iter().map(|x| x.to_string()).collect();
+        }
+        let path = Path::new(&args.golden).join(format!("{}_input.hex", tb));
+        let inputs = read_memh(&std::fs::read_to_string(&path)?)?;
+        expected_words = golden::reference(tb, &inputs);
+    }
+    if let Some(path) = &args.expected {
+        expected_words = Some(read_memh(&std::fs::read_to_string(path)?)?);
+    }
+    let expected_words =
+        expected_words.ok_or("No expected values, so use '--testbench' or '--expected'")?;
+
+    let find = |name: &str| vcd.find(name).ok_or(format!("Signal not found: {}", name));
+    let clk = find(clock.as_deref().unwrap_or("clock"))?;
+    let vld = valid.as_deref().map(find).transpose()?;
+    let mut probes = Vec::new();
+    for name in signals.iter().chain(show.iter()) {
+        probes.push(find(name)?);
+    }
+    if let Some(v) = vld {
+        probes.push(v);
+    }
+
+    let widths: Vec<usize> = probes[..signals.len()].iter().map(|s| s.width).collect();
+    let expected = split_words(&expected_words, &widths);
+
+    let edge = if args.falling {
+        Edge::Falling
+    } else {
+        Edge::Rising
+    };
+    let samples = vcd.sample(clk, edge, &probes);
+    let valids: Vec<&Sample> = samples
+        .iter()
+        .filter(|s| vld.is_none() || s.values.last().is_some_and(|x| x.is_high()))
+        .collect();
+    let compared: Vec<Sample> = valids
+        .iter()
+        .map(|s| Sample {
+            values: s.values[..signals.len()].to_vec(),
+            ..(*s).clone()
+        })
+        .collect();
+
+    println!(
+        "{}: {} cycles of '{}', {} valid outputs, {} expected",
+        args.vcd,
+        samples.len(),
+        clk.path(),
+        valids.len(),
+        expected.len()
+    );
+
+    let names: Vec<String> = probes.iter().map(|s| s.name.clone()).collect();
+    match compare(&compared.iter().collect::<Vec<_>>(), &expected) {
+        None => {
+            println!("All {} outputs match", expected.len());
+            if valids.len() > expected.len() {
+                println!(
+                    "Note: {} additional outputs were ignored",
+                    valids.len() - expected.len()
+                );
+            }
+            Ok(())
+        }
+        Some(mismatch) => {
+            match &mismatch.sample {
+                Some(s) => println!(
+                    "\nFirst mismatch at output {}, cycle {} (time {}):",
+                    mismatch.index, s.cycle, s.time
+                ),
+                None => println!(
+                    "\nMissing output {}, after {} valid outputs:",
+                    mismatch.index,
+                    valids.len()
+                ),
+            }
+            print_context(&mismatch, &samples, &valids, &names, args.context);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use num::complex::Complex;
+
+use crate::adc::{from_sign_magnitude, to_sign_magnitude};
+use crate::correlator::DataType;
+
+/* Raw antenna captures, as written to the DDR3 by 'rtl/tart/acquire.v'. Each
+ * sample is the (1-bit) '{Q[RADIOS-1:0], I[RADIOS-1:0]}' data, padded to
+ * 'RAD_KEEPS' bytes, and the bytes of consecutive samples are packed (little-
+ * endian) into 'AXI_WIDTH'-bit words, with no padding between samples. These
+ * are written using bursts of 'CHUNK' words, to consecutive addresses.
+ */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaptureLayout {
+    pub radios: usize,
+    pub axi_width: usize,
+    pub chunk: usize,
+}
+
+impl CaptureLayout {
+    /**
+     * Layout with the default 'CHUNK' size, of 512 bytes per burst, for at
+     * least one radio, and a whole number of bytes per AXI word.
+     */
+    pub fn new(radios: usize, axi_width: usize) -> Result<Self, String> {
+        if radios == 0 {
+            return Err("Captures need at least one radio".to_string());
+        }
+        if axi_width == 0 || !axi_width.is_multiple_of(8) {
+            return Err(format!("Invalid AXI width: {}", axi_width));
+        }
+        Ok(Self {
+            radios,
+            axi_width,
+            chunk: 512 * 8 / axi_width,
+        })
+    }
+
+    pub fn with_chunk(self, chunk: usize) -> Self {
+        Self { chunk, ..self }
+    }
+
+    /**
+     * Number of bytes for each sample, of all radios.
+     */
+    pub fn rad_keeps(&self) -> usize {
+        (self.radios * 2).div_ceil(8)
+    }
+
+    pub fn axi_keeps(&self) -> usize {
+        self.axi_width / 8
+    }
+
+    /**
+     * Number of bytes written by each burst, and the address-step between
+     * bursts ('ADDR_STEP').
+     */
+    pub fn chunk_bytes(&self) -> usize {
+        self.chunk * self.axi_keeps()
+    }
+
+    /**
+     * Extract the samples of each antenna from a memory dump, starting at the
+     * first burst. Only complete bursts are used, unless 'partial' is set (for
+     * streamed read-back), and then any trailing bytes of an incomplete sample
+     * are discarded.
+     */
+    pub fn decode(&self, bytes: &[u8], partial: bool) -> Capture {
+        let chunk = self.chunk_bytes().max(1);
+        let used = if partial {
+            bytes.len()
+        } else {
+            bytes.len() - bytes.len() % chunk
+        };
+        let keeps = self.rad_keeps();
+        let count = used / keeps;
+
+        let mut idata = vec![Vec::with_capacity(count); self.radios];
+        let mut qdata = vec![Vec::with_capacity(count); self.radios];
+
+        for sample in bytes[..count * keeps].chunks_exact(keeps) {
+            let bit = |k: usize| (sample[k >> 3] >> (k & 7)) & 1;
+            for a in 0..self.radios {
+                idata[a].push(bit(a));
+                qdata[a].push(bit(self.radios + a));
+            }
+        }
+
+        Capture {
+            radios: self.radios,
+            idata,
+            qdata,
+            discarded: bytes.len() - count * keeps,
+        }
+    }
+
+    /**
+     * Memory image of the (1-bit) signals, in the same layout as 'acquire',
+     * with the last burst zero-padded.
+     */
+    pub fn encode(&self, signals: &[Vec<DataType>]) -> Vec<u8> {
+        let count = signals.iter().map(|s| s.len()).min().unwrap_or(0);
+        let keeps = self.rad_keeps();
+        let mut bytes = Vec::with_capacity(count * keeps);
+
+        for t in 0..count {
+            let mut sample = vec![0u8; keeps];
+            let mut set = |k: usize, x: i32| sample[k >> 3] |= (x as u8) << (k & 7);
+            for (a, s) in signals.iter().enumerate() {
+                set(a, to_sign_magnitude(s[t].re, 1));
+                set(self.radios + a, to_sign_magnitude(s[t].im, 1));
+            }
+            bytes.extend_from_slice(&sample);
+        }
+
+        let chunk = self.chunk_bytes().max(1);
+        bytes.resize(bytes.len().div_ceil(chunk) * chunk, 0);
+        bytes
+    }
+}
+
+/**
+ * The I- & Q- (sign) bits of each antenna, from a capture.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capture {
+    pub radios: usize,
+    pub idata: Vec<Vec<u8>>,
+    pub qdata: Vec<Vec<u8>>,
+    pub discarded: usize,
+}
+
+impl Capture {
+    pub fn num_samples(&self) -> usize {
+        self.idata.first().map(|x| x.len()).unwrap_or(0)
+    }
+
+    /**
+     * The ADC values of each antenna, as used by the reference correlator.
+     */
+    pub fn signals(&self) -> Vec<Vec<DataType>> {
+        self.idata
+            .iter()
+            .zip(self.qdata.iter())
+            .map(|(i, q)| {
+                i.iter()
+                    .zip(q.iter())
+                    .map(|(&x, &y)| {
+                        Complex::new(
+                            from_sign_magnitude(x as i32, 1),
+                            from_sign_magnitude(y as i32, 1),
+                        )
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_zero_radios() {
+        assert!(CaptureLayout::new(0, 32).is_err());
+    }
+
+    #[test]
+    fn round_trip_with_partial_burst() {
+        let (radios, count) = (100, 30);
+        let layout = CaptureLayout::new(radios, 32).unwrap();
+        let level = |x: usize| if x & 1 == 0 { 1 } else { -1 };
+        let signals: Vec<Vec<DataType>> = (0..radios)
+            .map(|a| {
+                (0..count)
+                    .map(|t| Complex::new(level(a * 7 + t), level(a + t * 3 + (t >> 1))))
+                    .collect()
+            })
+            .collect();
+
+        // The 25-byte samples end part-way through the second 512-byte burst
+        let keeps = layout.rad_keeps();
+        let bytes = layout.encode(&signals);
+        assert_eq!(bytes.len(), 2 * layout.chunk_bytes());
+        let bytes = &bytes[..count * keeps];
+
+        let capture = layout.decode(bytes, true);
+        assert_eq!(capture.discarded, 0);
+        assert_eq!(capture.signals(), signals);
+
+        // Only the complete first burst is used, unless 'partial' is set
+        let capture = layout.decode(bytes, false);
+        let used = layout.chunk_bytes() / keeps;
+        assert_eq!(capture.num_samples(), used);
+        assert_eq!(capture.discarded, bytes.len() - used * keeps);
+        let firsts: Vec<Vec<DataType>> = signals.iter().map(|s| s[..used].to_vec()).collect();
+        assert_eq!(capture.signals(), firsts);
+    }
+}
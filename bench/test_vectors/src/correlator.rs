@@ -0,0 +1,107 @@
+use num::complex::Complex;
+
+use crate::adc::to_sign_magnitude;
+use crate::truth::TruthTable;
+
+/* Reference models for the correlator. The 'correlate' function computes the
+ * visibilities from the ADC values, and the 'Correlator' model computes them
+ * from the sign/magnitude codes, using the same lookup ROM as the hardware.
+ */
+
+pub type DataType = Complex<i32>;
+
+pub fn correlate(a: &[DataType], b: &[DataType]) -> DataType {
+    // Complex Correlation of antennas a and b signals
+
+    let mut re: i32 = 0;
+    let mut im: i32 = 0;
+
+    for (x, y) in a.iter().zip(b.iter()) {
+        let z = x * y.conj();
+        re += z.re;
+        im += z.im;
+    }
+
+    Complex::new(re, im)
+}
+
+/**
+ * Model of the correlator datapath, where each product is looked up from the
+ * truth-table ROM, and then accumulated.
+ *
+ * In offset-binary mode, each (unsigned) product has the 'bias' added, so that
+ * the final accumulator values (ACCUM) are larger by 'samples * bias' than the
+ * two's-complement values, and this is corrected for at the end.
+ */
+#[derive(Debug, Clone)]
+pub struct Correlator {
+    pub bits: u8,
+    pub offset: bool,
+    table: TruthTable,
+}
+
+impl Correlator {
+    pub fn new(bits: u8, offset: bool) -> Self {
+        Self {
+            bits,
+            offset,
+            table: TruthTable::new(bits, offset),
+        }
+    }
+
+    /**
+     * The ROM address for the product of the given (ADC) samples.
+     */
+    pub fn address(&self, a: DataType, b: DataType) -> usize {
+        let n = self.bits as usize;
+        let codes = [
+            to_sign_magnitude(a.re, self.bits),
+            to_sign_magnitude(a.im, self.bits),
+            to_sign_magnitude(b.re, self.bits),
+            to_sign_magnitude(b.im, self.bits),
+        ];
+        codes
+            .iter()
+            .fold(0, |addr, &code| (addr << n) | code as usize)
+    }
+
+    /**
+     * Encoded product, as stored within the ROM, for the given samples.
+     */
+    pub fn product(&self, a: DataType, b: DataType) -> (i64, i64) {
+        let row = &self.table.rows[self.address(a, b)];
+        let bias = self.table.bias();
+        ((row.re + bias) as i64, (row.im + bias) as i64)
+    }
+
+    /**
+     * Raw (ACCUM) accumulator values, as computed by the hardware.
+     */
+    pub fn accumulate(&self, a: &[DataType], b: &[DataType]) -> (i64, i64) {
+        a.iter()
+            .zip(b.iter())
+            .map(|(&x, &y)| self.product(x, y))
+            .fold((0, 0), |(re, im), (r, i)| (re + r, im + i))
+    }
+
+    /**
+     * Correction term that is subtracted from each of the Re & Im ACCUM
+     * values, after accumulating 'samples' products.
+     */
+    pub fn correction(&self, samples: usize) -> i64 {
+        self.table.bias() as i64 * samples as i64
+    }
+
+    /**
+     * Visibility, after applying the offset correction to the ACCUM values.
+     */
+    pub fn correct(&self, accum: (i64, i64), samples: usize) -> Complex<i64> {
+        let bias = self.correction(samples);
+        Complex::new(accum.0 - bias, accum.1 - bias)
+    }
+
+    pub fn visibility(&self, a: &[DataType], b: &[DataType]) -> Complex<i64> {
+        let samples = a.len().min(b.len());
+        self.correct(self.accumulate(a, b), samples)
+    }
+}
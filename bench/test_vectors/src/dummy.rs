@@ -0,0 +1,114 @@
+use num::complex::Complex;
+
+use crate::adc::from_sign_magnitude;
+use crate::correlator::DataType;
+
+/* Model of the 'radio_dummy' modules (in 'rtl/radio/radio.v'), that drive the
+ * correlator of 'fake_tart_8x_radio', in place of the radios. Each of the I & Q
+ * signals is a 10-bit XNOR LFSR, with taps 10 and 7, seeded with 'ANT_NUM + 1'
+ * (I) and 'ANT_NUM + 3' (Q), so that the Q signal of antenna 'n' is the I
+ * signal of antenna 'n + 2'. Bit 2 of each LFSR is registered, as the output.
+ *
+ * The LFSR 'reg [10:1]' is stored with bit 'k' of the register at bit 'k - 1'.
+ */
+
+const LFSR_MASK: u16 = 0x3ff;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RadioDummy {
+    pub ant_num: usize,
+    i_lfsr: u16,
+    q_lfsr: u16,
+    data_i: u8,
+    data_q: u8,
+}
+
+fn lfsr_step(lfsr: u16) -> u16 {
+    let xnor = !((lfsr >> 9) ^ (lfsr >> 6)) & 1;
+    ((lfsr << 1) | xnor) & LFSR_MASK
+}
+
+impl RadioDummy {
+    /**
+     * The state at power-on (or reset), with the outputs low.
+     */
+    pub fn new(ant_num: usize) -> Self {
+        Self {
+            ant_num,
+            i_lfsr: (ant_num as u16 + 1) & LFSR_MASK,
+            q_lfsr: (ant_num as u16 + 3) & LFSR_MASK,
+            data_i: 0,
+            data_q: 0,
+        }
+    }
+
+    /**
+     * The registered '(data_i, data_q)' outputs.
+     */
+    pub fn output(&self) -> (u8, u8) {
+        (self.data_i, self.data_q)
+    }
+
+    /**
+     * Advance by one 'clk16' cycle.
+     */
+    pub fn clock(&mut self) {
+        self.data_i = (self.i_lfsr >> 1) as u8 & 1;
+        self.data_q = (self.q_lfsr >> 1) as u8 & 1;
+        self.i_lfsr = lfsr_step(self.i_lfsr);
+        self.q_lfsr = lfsr_step(self.q_lfsr);
+    }
+}
+
+/**
+ * The 1-bit '(I, Q)' signals, as ADC values, of the 'dat_i' & 'dat_q' registers
+ * of 'fake_tart_8x_radio', which add another register stage after each
+ * 'radio_dummy'. The first 'skip' cycles after power-on are discarded.
+ */
+pub fn fake_tart_signals(antennas: usize, samples: usize, skip: usize) -> Vec<Vec<DataType>> {
+    let mut radios: Vec<RadioDummy> = (0..antennas).map(RadioDummy::new).collect();
+    let mut dat = vec![(0, 0); antennas];
+    let mut signals = vec![Vec::with_capacity(samples); antennas];
+
+    for cycle in 0..skip + samples {
+        if cycle >= skip {
+            for (signal, &(i, q)) in signals.iter_mut().zip(dat.iter()) {
+                signal.push(Complex::new(
+                    from_sign_magnitude(i as i32, 1),
+                    from_sign_magnitude(q as i32, 1),
+                ));
+            }
+        }
+        for (d, radio) in dat.iter_mut().zip(radios.iter_mut()) {
+            *d = radio.output();
+            radio.clock();
+        }
+    }
+    signals
+}
+
+/**
+ * Raw accumulator values of the (1-bit) correlator, 'rtl/correlator/
+ * correlate.v', for each visibility, where each sample adds a '{0, 1, 2}' term
+ * to each of the Re & Im components, so that these are the two's-complement
+ * visibility (halved), plus the number of samples.
+ */
+pub fn raw_visibility(a: &[DataType], b: &[DataType]) -> (i64, i64) {
+    let samples = a.len().min(b.len()) as i64;
+    let (re, im) = a
+        .iter()
+        .zip(b.iter())
+        .map(|(x, y)| x * y.conj())
+        .fold((0, 0), |(re, im), z| (re + z.re as i64, im + z.im as i64));
+    (samples + re / 2, samples + im / 2)
+}
+
+/**
+ * Raw signal-mean value, of the correlator, which is the number of high (so,
+ * negative) I & Q bits.
+ */
+pub fn raw_mean(a: &[DataType]) -> i64 {
+    a.iter()
+        .map(|z| (z.re < 0) as i64 + (z.im < 0) as i64)
+        .sum()
+}
@@ -0,0 +1,410 @@
+use rand::Rng;
+
+/* Golden, stage-by-stage expected values for the correlator testbenches. The
+ * input vectors, and the expected outputs of each stage, are written as
+ * '$readmemh' files, so that each testbench can replay the same inputs, and
+ * then check its outputs, instead of relying on waveform inspection.
+ *
+ * Each input vector is the word '{auto, Q[WIDTH-1:0], I[WIDTH-1:0]}', of the
+ * (1-bit) antenna signals, and the signal-means flag for the correlators.
+ */
+
+/**
+ * Sigsource configuration, with the same meaning as the 'ATAPS', 'BTAPS',
+ * 'ASELS', and 'BSELS' parameters of 'rtl/correlator/sigsource.v', but with
+ * index 0 as the first entry of each list.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MuxConfig {
+    pub width: usize,
+    pub trate: usize,
+    pub ataps: Vec<usize>,
+    pub btaps: Vec<usize>,
+    pub asels: Vec<usize>,
+    pub bsels: Vec<usize>,
+}
+
+impl MuxConfig {
+    /**
+     * The A- & B- signal bits, '{ai, aq, bi, bq}', selected for the time-slot
+     * 'taddr'.
+     */
+    pub fn select(&self, taddr: usize, idata: u64, qdata: u64) -> u8 {
+        let a = self.ataps[self.asels[taddr]];
+        let b = self.btaps[self.bsels[taddr]];
+        let bit = |x: u64, i: usize| ((x >> i) & 1) as u8;
+        (bit(idata, a) << 3) | (bit(qdata, a) << 2) | (bit(idata, b) << 1) | bit(qdata, b)
+    }
+}
+
+/**
+ * The (unsigned) Re & Im terms of the 1-bit 'correlate' core, for the bits
+ * '{ai, aq, bi, bq}', where each cross-correlation term is within {0, 1, 2},
+ * and is half of the offset-binary product. For the signal-means, the terms
+ * are the number of set A- and B- bits.
+ */
+pub fn correlate_bits(bits: u8, auto: bool) -> (u64, u64) {
+    let (ai, aq, bi, bq) = (bits >> 3 & 1, bits >> 2 & 1, bits >> 1 & 1, bits & 1);
+    if auto {
+        return ((ai + aq) as u64, (bi + bq) as u64);
+    }
+    let term = |inc: bool, dec: bool| {
+        if inc {
+            2
+        } else if dec {
+            0
+        } else {
+            1
+        }
+    };
+    let re = term(ai == bi && aq == bq, ai != bi && aq != bq);
+    let im = term(aq == bi && ai != bq, aq != bi && ai == bq);
+    (re, im)
+}
+
+fn mask(width: usize) -> u64 {
+    if width >= 64 {
+        u64::MAX
+    } else {
+        (1 << width) - 1
+    }
+}
+
+/**
+ * Datapath model of a single correlator, for the signal-source MUXs, then the
+ * partial-sums of every 'count' samples (of the same time-slot), and then the
+ * accumulated visibilities of each time-slot (over all passes).
+ */
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    pub mux: MuxConfig,
+    pub count: usize,
+    pub autos: Vec<bool>,
+    pub sbits: usize,
+    pub accum: usize,
+}
+
+impl Pipeline {
+    /**
+     * Time-slot of the n-th input sample, as each block of 'count' samples is
+     * from the same time-slot.
+     */
+    pub fn taddr(&self, n: usize) -> usize {
+        (n / self.count) % self.mux.trate
+    }
+
+    pub fn auto(&self, n: usize) -> bool {
+        self.autos.get(self.taddr(n)).copied().unwrap_or(false)
+    }
+
+    /**
+     * The (I, Q) samples of the given input vectors.
+     */
+    pub fn samples(&self, inputs: &[u64]) -> Vec<(u64, u64)> {
+        let w = self.mux.width;
+        inputs
+            .iter()
+            .map(|&x| (x & mask(w), (x >> w) & mask(w)))
+            .collect()
+    }
+
+    /**
+     * Input vectors, '{auto, Q, I}', for the given (I, Q) samples.
+     */
+    pub fn inputs(&self, samples: &[(u64, u64)]) -> Vec<u64> {
+        let w = self.mux.width;
+        samples
+            .iter()
+            .enumerate()
+            .map(|(n, &(i, q))| (self.auto(n) as u64) << (2 * w) | q << w | i)
+            .collect()
+    }
+
+    pub fn mux_bits(&self, samples: &[(u64, u64)]) -> Vec<u8> {
+        samples
+            .iter()
+            .enumerate()
+            .map(|(n, &(i, q))| self.mux.select(self.taddr(n), i, q))
+            .collect()
+    }
+
+    /**
+     * The '{re, im}' partial-sums, of each (complete) block of 'count' samples,
+     * wrapped to 'sbits'.
+     */
+    pub fn partial_sums(&self, samples: &[(u64, u64)]) -> Vec<(u64, u64)> {
+        let m = mask(self.sbits);
+        self.mux_bits(samples)
+            .chunks_exact(self.count)
+            .enumerate()
+            .map(|(k, block)| {
+                let auto = self.auto(k * self.count);
+                block
+                    .iter()
+                    .map(|&bits| correlate_bits(bits, auto))
+                    .fold((0, 0), |(r, i), (x, y)| ((r + x) & m, (i + y) & m))
+            })
+            .collect()
+    }
+
+    /**
+     * The '{re, im}' visibilities of each time-slot, accumulated over all of
+     * the (complete) passes, and wrapped to 'accum'.
+     */
+    pub fn visibilities(&self, samples: &[(u64, u64)]) -> Vec<(u64, u64)> {
+        let m = mask(self.accum);
+        let sums = self.partial_sums(samples);
+        let passes = sums.len() / self.mux.trate;
+        let mut vis = vec![(0, 0); self.mux.trate];
+        for block in sums.chunks_exact(self.mux.trate).take(passes) {
+            for (v, &(r, i)) in vis.iter_mut().zip(block.iter()) {
+                *v = ((v.0 + r) & m, (v.1 + i) & m);
+            }
+        }
+        vis
+    }
+}
+
+/**
+ * Sum each of the 'nsums' interleaved inputs, over all (complete) passes, and
+ * wrapped to 'obits', as for the 'visfinal' unit.
+ */
+pub fn accumulate(data: &[u64], nsums: usize, obits: usize) -> Vec<u64> {
+    let m = mask(obits);
+    let mut sums = vec![0; nsums];
+    for pass in data.chunks_exact(nsums) {
+        for (s, &x) in sums.iter_mut().zip(pass.iter()) {
+            *s = (*s + x) & m;
+        }
+    }
+    sums
+}
+
+/**
+ * Contents of a '$readmemh' file, with one 'width'-bit word per line.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemFile {
+    pub name: String,
+    pub comment: String,
+    pub width: usize,
+    pub words: Vec<u64>,
+}
+
+impl MemFile {
+    pub fn new(name: &str, comment: &str, width: usize, words: Vec<u64>) -> Self {
+        Self {
+            name: name.to_string(),
+            comment: comment.to_string(),
+            width,
+            words,
+        }
+    }
+
+    pub fn to_readmemh(&self) -> String {
+        let digits = self.width.div_ceil(4).max(1);
+        let mut out = String::new();
+        for line in self.comment.lines() {
+            out.push_str(&format!("// {}\n", line));
+        }
+        out.push_str(&format!(
+            "// {} words, of {} bits\n",
+            self.words.len(),
+            self.width
+        ));
+        for word in self.words.iter() {
+            out.push_str(&format!("{:0digits$x}\n", word, digits = digits));
+        }
+        out
+    }
+}
+
+fn pack(pairs: &[(u64, u64)], width: usize) -> Vec<u64> {
+    pairs.iter().map(|&(r, i)| r << width | i).collect()
+}
+
+fn random_samples(rng: &mut impl Rng, width: usize, n: usize) -> Vec<(u64, u64)> {
+    (0..n)
+        .map(|_| {
+            (
+                rng.gen::<u64>() & mask(width),
+                rng.gen::<u64>() & mask(width),
+            )
+        })
+        .collect()
+}
+
+/**
+ * Stage outputs of a 'Pipeline', for the given testbench name: the input
+ * vectors, the MUX-selected bits, the partial-sums, and the visibilities.
+ */
+fn pipeline_files(name: &str, pipe: &Pipeline, samples: &[(u64, u64)]) -> Vec<MemFile> {
+    let w = pipe.mux.width;
+    let mux = pipe.mux_bits(samples).iter().map(|&x| x as u64).collect();
+    vec![
+        MemFile::new(
+            &format!("{}_input.hex", name),
+            "Input vectors: {auto, Q, I}",
+            2 * w + 1,
+            pipe.inputs(samples),
+        ),
+        MemFile::new(
+            &format!("{}_mux.hex", name),
+            "MUX-selected signals, per cycle: {ai, aq, bi, bq}",
+            4,
+            mux,
+        ),
+        MemFile::new(
+            &format!("{}_sums.hex", name),
+            &format!("Partial-sums, every {} samples: {{re, im}}", pipe.count),
+            2 * pipe.sbits,
+            pack(&pipe.partial_sums(samples), pipe.sbits),
+        ),
+        MemFile::new(
+            &format!("{}_vis.hex", name),
+            "Accumulated visibilities, per time-slot: {re, im}",
+            2 * pipe.accum,
+            pack(&pipe.visibilities(samples), pipe.accum),
+        ),
+    ]
+}
+
+/**
+ * Configuration of 'rtl/correlator/sigsource_tb.v', with 12x antennas and 6:1
+ * time-multiplexing, with one pass of 15 samples for each time-slot.
+ */
+pub fn sigsource_pipeline() -> Pipeline {
+    let mux = MuxConfig {
+        width: 12,
+        trate: 6,
+        ataps: vec![0xb, 0x8, 0x7, 0x5, 0x1],
+        btaps: vec![0xa, 0x9, 0x3, 0x2, 0x0],
+        asels: vec![0, 4, 3, 2, 1, 0],
+        bsels: vec![3, 0, 4, 2, 1, 1],
+    };
+    Pipeline {
+        mux,
+        count: 15,
+        autos: vec![false; 6],
+        sbits: 6,
+        accum: 6,
+    }
+}
+
+pub fn sigsource_tb(rng: &mut impl Rng) -> Vec<MemFile> {
+    let pipe = sigsource_pipeline();
+    let samples = random_samples(rng, 12, 6 * 15);
+    pipeline_files("sigsource_tb", &pipe, &samples)
+}
+
+/**
+ * Configuration of 'rtl/correlator/correlate_tb.v', where the A- & B- signals
+ * are the bits 0 & 1 (respectively) of the inputs, with 12 time-slots of 3
+ * samples, and with the last two time-slots computing signal-means.
+ */
+pub fn correlate_pipeline() -> Pipeline {
+    let mux = MuxConfig {
+        width: 2,
+        trate: 12,
+        ataps: vec![0],
+        btaps: vec![1],
+        asels: vec![0; 12],
+        bsels: vec![0; 12],
+    };
+    let autos = (0..12).map(|t| t >= 10).collect();
+    Pipeline {
+        mux,
+        count: 3,
+        autos,
+        sbits: 4,
+        accum: 4,
+    }
+}
+
+pub fn correlate_tb(rng: &mut impl Rng) -> Vec<MemFile> {
+    let pipe = correlate_pipeline();
+    let samples = random_samples(rng, 2, 12 * 3);
+    pipeline_files("correlate_tb", &pipe, &samples)
+}
+
+// 'visfinal_tb' configuration: 'IBITS', 'OBITS', 'NSUMS', and passes
+const VISFINAL: (usize, usize, usize, usize) = (5, 8, 4, 8);
+
+/**
+ * Golden files for 'rtl/correlator/visfinal_tb.v', with 4x interleaved, 5-bit
+ * partial-sums, accumulated over 8 passes, into 8-bit visibilities.
+ */
+pub fn visfinal_tb(rng: &mut impl Rng) -> Vec<MemFile> {
+    let (ibits, obits, nsums, passes) = VISFINAL;
+    let data: Vec<u64> = (0..nsums * passes)
+        .map(|_| rng.gen::<u64>() & mask(ibits))
+        .collect();
+    let sums = accumulate(&data, nsums, obits);
+    vec![
+        MemFile::new(
+            "visfinal_tb_input.hex",
+            "Interleaved partial-sums",
+            ibits,
+            data,
+        ),
+        MemFile::new(
+            "visfinal_tb_vis.hex",
+            "Accumulated visibilities",
+            obits,
+            sums,
+        ),
+    ]
+}
+
+/**
+ * Input vectors for 'bench/tart_correlator_tb.v', with two banks of 15 samples,
+ * from 16x antennas. There are no expected outputs, as the correlator taps of
+ * 'tart_correlator' are not yet parameters, so its output order is not known.
+ */
+pub fn tart_correlator_tb(rng: &mut impl Rng) -> Vec<MemFile> {
+    let (width, count) = (16, 15);
+    let samples = random_samples(rng, width, 2 * count);
+    let inputs = samples.iter().map(|&(i, q)| q << width | i).collect();
+    vec![MemFile::new(
+        "tart_correlator_tb_input.hex",
+        "Input vectors: {Q, I}",
+        2 * width,
+        inputs,
+    )]
+}
+
+/**
+ * Golden files for the testbenches that check their outputs.
+ */
+pub fn testbenches(rng: &mut impl Rng) -> Vec<MemFile> {
+    let mut files = sigsource_tb(rng);
+    files.extend(correlate_tb(rng));
+    files.extend(visfinal_tb(rng));
+    files
+}
+
+/**
+ * Expected outputs, as checked by the named testbench, computed from its input
+ * vectors using the reference models: the MUX-selected signals for
+ * 'sigsource_tb', the partial-sums for 'correlate_tb', and the visibilities for
+ * 'visfinal_tb'.
+ */
+pub fn reference(name: &str, inputs: &[u64]) -> Option<Vec<u64>> {
+    match name {
+        "sigsource_tb" => {
+            let pipe = sigsource_pipeline();
+            let mux = pipe.mux_bits(&pipe.samples(inputs));
+            Some(mux.iter().map(|&x| x as u64).collect())
+        }
+        "correlate_tb" => {
+            let pipe = correlate_pipeline();
+            let sums = pipe.partial_sums(&pipe.samples(inputs));
+            Some(pack(&sums, pipe.sbits))
+        }
+        "visfinal_tb" => {
+            let (_, obits, nsums, _) = VISFINAL;
+            Some(accumulate(inputs, nsums, obits))
+        }
+        _ => None,
+    }
+}
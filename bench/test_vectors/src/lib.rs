@@ -0,0 +1,8 @@
+pub mod adc;
+pub mod capture;
+pub mod correlator;
+pub mod dummy;
+pub mod golden;
+pub mod sky;
+pub mod truth;
+pub mod vcd;
@@ -4,31 +4,36 @@ use rand::distributions::{Distribution, Uniform};
 use rand::Rng;
 use std::fs::File;
 use std::io::{BufWriter, Write};
+use test_vectors::adc::to_sign_magnitude;
+use test_vectors::capture::CaptureLayout;
+use test_vectors::correlator::{correlate, Correlator, DataType};
+use test_vectors::dummy::{fake_tart_signals, raw_mean, raw_visibility};
+use test_vectors::golden;
+use test_vectors::sky::{
+    antenna_signals, parse_positions, PointSource, L1_FREQUENCY, SPEED_OF_LIGHT,
+};
+use test_vectors::truth::TruthTable;
 
 /* Given an antenna, generate a random list of sample values
  * Store these in radio_data: an array of n_ant, n_samples.
  * Write these to a verilog test vector file 'radio_data.txt'
  *
- * generate the complex correlation products for each pair (i,j) and write these
- * a file called 'vis_data.txt' TODO At the moment these are just displayed.
+ * generate the complex correlation products for each pair (i,j), and display
+ * these, and optionally write them to a (CSV) file, using '--vis'.
  */
 
-type DataType = Complex<i32>;
-
 /**
  * Create an ADC sample (random) that matches the range of samples
- * that we can expect from the MA2769. NOTE. This means that for 2-bit
- * data the range will be greater (between -5 and 5) and I'm a little unsure
- * about what scaling means in this case. I suspect that as we have Automatic
- * gain control, the scaling is not significant, and we can rescale down
- * to something suitable, so perhaps can use a range of -2,-1,1,2 just as well
- * although we have to be careful. 1-bit is just 1 if negative and 0 if positive.
+ * that we can expect from the MA2769. For 2-bit data, these are the levels
+ * -3,-1,1,3 (see 'adc::to_sign_magnitude'). 1-bit is just 1 if negative and 0
+ * if positive.
  *
  * The Automatic Gain Control keeps the magnitude bit high 33% of the time in 2-bit
- * mode. This means we should generate random samples with this property.
+ * mode. This means we should generate random samples with this property, so an
+ * odd value within -5..5 is drawn, and only +/-5 set the magnitude bit.
  * */
 fn adc_sample(rng: &mut impl Rng, bits: u8) -> i32 {
-    let mut s = 0;
+    let mut s: i32 = 0;
     let mut min = -1;
     let mut max = 1;
 
@@ -49,35 +54,12 @@ fn adc_sample(rng: &mut impl Rng, bits: u8) -> i32 {
     while s % 2 == 0 {
         s = sign_mag.sample(rng); // the radio itself never produces zeros, or even numbers.
     }
-    return s;
-}
-
-fn to_sign_magnitude(a: i32, nbits: u8) -> i32 {
-    // See Table 16 in the Max2769 data sheet.
-    let mut ret = 0;
 
-    match nbits {
-        1 => {
-            if a < 0 {
-                ret = 1;
-            } else {
-                ret = 0;
-            }
-        }
-        2 => {
-            if a < -3 {
-                ret = 0b11;
-            } else if a < 0 {
-                ret = 0b10;
-            } else if a < 4 {
-                ret = 0b00;
-            } else {
-                ret = 0b01;
-            }
-        }
-        _ => println!("Only work with 1 or two bit sign magnitude data"),
+    if s.abs() > 3 {
+        3 * s.signum()
+    } else {
+        s.signum()
     }
-    return ret;
 }
 
 fn create_data(n: usize, bits: u8) -> Vec<DataType> {
@@ -96,22 +78,7 @@ fn create_data(n: usize, bits: u8) -> Vec<DataType> {
     }
 
     println!("Percent Mag Hi: {}\n", (count as f32) / (n as f32));
-    return buffer;
-}
-
-fn correlate(a: &Vec<DataType>, b: &Vec<DataType>) -> DataType {
-    // Complex Correlation of antennas a and b signals
-
-    let mut re: i32 = 0;
-    let mut im: i32 = 0;
-
-    for i in 0..a.capacity() {
-        let z = a[i] * b[i].conj();
-        re += z.re;
-        im += z.im;
-    }
-
-    return Complex::new(re, im);
+    buffer
 }
 
 #[derive(Parser, Debug)]
@@ -132,62 +99,307 @@ struct Args {
     /// Number of samples to generate
     #[arg(short, long, default_value_t = 1024)]
     samples: usize,
+
+    /// Write the correlator truth-tables (Markdown, Verilog, and ROM image),
+    /// for the given number of ADC bits, to files with this prefix
+    #[arg(short, long)]
+    truth: Option<String>,
+
+    /// Use offset-binary, instead of two's-complement, truth-table outputs,
+    /// and also output the (offset) accumulator values for each visibility
+    #[arg(short, long, default_value_t = false)]
+    offset: bool,
+
+    /// Raw DDR3 capture (as written by 'acquire') to correlate, instead of
+    /// generating random data, with '--ant' radios
+    #[arg(short, long)]
+    capture: Option<String>,
+
+    /// AXI data-width of the capture ('AXI_WIDTH')
+    #[arg(long, default_value_t = 32)]
+    axi_width: usize,
+
+    /// Number of AXI words per burst ('CHUNK') [default: 4096 / AXI_WIDTH]
+    #[arg(long)]
+    chunk: Option<usize>,
+
+    /// Also decode any trailing, incomplete burst of the capture
+    #[arg(long, default_value_t = false)]
+    partial: bool,
+
+    /// Write the golden input vectors, and expected stage outputs, of the
+    /// correlator testbenches, as '$readmemh' files, to this directory
+    #[arg(short, long)]
+    golden: Option<String>,
+
+    /// Sky-model test vectors, of a point source at the direction-cosines
+    /// 'l,m' (east, north), instead of random data (requires '--positions')
+    #[arg(long, allow_hyphen_values = true)]
+    source: Option<String>,
+
+    /// Antenna positions file (YAML or JSON), of (east, north, up) offsets,
+    /// in metres, for the sky-model
+    #[arg(long)]
+    positions: Option<String>,
+
+    /// Signal-to-noise ratio of the point source, at each antenna
+    #[arg(long, default_value_t = 1.0)]
+    snr: f64,
+
+    /// Observing frequency of the sky-model, in Hz
+    #[arg(long, default_value_t = L1_FREQUENCY)]
+    frequency: f64,
+
+    /// Write the visibilities, as 'a,b,re,im', to this (CSV) file
+    #[arg(short, long)]
+    vis: Option<String>,
+
+    /// Use the PRN signals of the 'radio_dummy' sources of 'fake_tart_8x_radio',
+    /// instead of random data
+    #[arg(long, default_value_t = false)]
+    fake: bool,
+
+    /// Number of cycles (after power-on) of the fake radios to skip
+    #[arg(long, default_value_t = 0)]
+    skip: usize,
+
+    /// Write the raw correlator values (of the 1-bit 'correlate.v'), and the
+    /// signal-means (as the 'a == b' rows), as 'a,b,re,im', to this (CSV) file
+    #[arg(long)]
+    raw: Option<String>,
+}
+
+/* Write the truth-table for 'bits'-bit data to '<prefix>_<bits>bit.md', '.v',
+ * and '.mem' files.
+ */
+fn write_truth_tables(prefix: &str, bits: u8, offset: bool) -> std::io::Result<()> {
+    let table = TruthTable::new(bits, offset);
+    let name = format!("{}_{}bit", prefix, bits);
+
+    std::fs::write(format!("{}.md", name), table.to_markdown())?;
+    std::fs::write(format!("{}.v", name), table.to_verilog())?;
+    std::fs::write(format!("{}.mem", name), table.to_readmemb())?;
+    println!("Truth-tables written to {}.{{md,v,mem}}", name);
+    Ok(())
+}
+
+/* Write the golden '$readmemh' files for each of the testbenches, into 'dir',
+ * and just the input vectors for 'tart_correlator_tb', which is not checked.
+ */
+fn write_golden(dir: &str) -> std::io::Result<()> {
+    let mut rng = rand::thread_rng();
+    std::fs::create_dir_all(dir)?;
+    let mut files = golden::testbenches(&mut rng);
+    files.extend(golden::tart_correlator_tb(&mut rng));
+    for file in files {
+        let path = std::path::Path::new(dir).join(&file.name);
+        std::fs::write(&path, file.to_readmemh())?;
+        println!("Golden vectors written to {}", path.display());
+    }
+    Ok(())
+}
+
+/* Print the correlations of each pair of antennas, and for offset-binary
+ * accumulation, the expected accumulator values.
+ */
+fn print_correlations(data: &[Vec<DataType>], bits: u8, offset: bool) {
+    let samples = data.iter().map(|x| x.len()).min().unwrap_or(0);
+
+    for i in 0..data.len() {
+        for j in i..data.len() {
+            let z = correlate(&data[i], &data[j]);
+            println!("{},{} = {:?}", i, j, z);
+        }
+    }
+
+    // Expected accumulator values, for offset-binary partial-sums
+    if offset {
+        let model = Correlator::new(bits, true);
+        println!(
+            "\nOffset-binary ACCUM values (correction: {}):",
+            model.correction(samples)
+        );
+        for i in 0..data.len() {
+            for j in i..data.len() {
+                let accum = model.accumulate(&data[i], &data[j]);
+                let z = model.correct(accum, samples);
+                println!(
+                    "{},{} = {:?} (ACCUM: re: {}, im: {})",
+                    i, j, z, accum.0, accum.1
+                );
+            }
+        }
+    }
+}
+
+/* Write the correlations of each pair of antennas, for 'a <= b', as CSV.
+ */
+fn write_visibilities(path: &str, data: &[Vec<DataType>]) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "a,b,re,im")?;
+    for i in 0..data.len() {
+        for j in i..data.len() {
+            let z = correlate(&data[i], &data[j]);
+            writeln!(writer, "{},{},{},{}", i, j, z.re, z.im)?;
+        }
+    }
+    writer.flush()?;
+    println!("Visibilities written to {}", path);
+    Ok(())
+}
+
+/* Write the raw (1-bit) correlator values of each (ordered) pair of antennas,
+ * as these cannot be conjugated without the number of samples, and as the 'a
+ * == b' rows, the signal-means, as CSV.
+ */
+fn write_raw(path: &str, data: &[Vec<DataType>]) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "a,b,re,im")?;
+    for i in 0..data.len() {
+        for j in 0..data.len() {
+            let (re, im) = if i == j {
+                (raw_mean(&data[i]), 0)
+            } else {
+                raw_visibility(&data[i], &data[j])
+            };
+            writeln!(writer, "{},{},{},{}", i, j, re, im)?;
+        }
+    }
+    writer.flush()?;
+    println!("Raw correlator values written to {}", path);
+    Ok(())
+}
+
+/* Sky-model antenna signals, of a point source.
+ */
+fn create_sky_data(source: &str, args: &Args) -> std::io::Result<Vec<Vec<DataType>>> {
+    let invalid = |e: String| std::io::Error::new(std::io::ErrorKind::InvalidInput, e);
+    let path = args
+        .positions
+        .as_ref()
+        .ok_or_else(|| invalid("A sky-model requires '--positions'".to_string()))?;
+    let positions = parse_positions(&std::fs::read_to_string(path)?).map_err(invalid)?;
+    let source = PointSource::parse(source, args.snr).map_err(invalid)?;
+
+    println!(
+        "Point source at (l, m) = ({}, {}), SNR {}, for {} antennas\n",
+        source.l,
+        source.m,
+        source.snr,
+        positions.len()
+    );
+    let wavelength = SPEED_OF_LIGHT / args.frequency;
+    let mut rng = rand::thread_rng();
+    Ok(antenna_signals(
+        &mut rng,
+        &positions,
+        wavelength,
+        &source,
+        args.samples,
+        args.bits,
+    ))
+}
+
+/* Decode a raw capture of 1-bit antenna data, and correlate it.
+ */
+fn correlate_capture(path: &str, args: &Args) -> std::io::Result<()> {
+    let invalid = |e: String| std::io::Error::new(std::io::ErrorKind::InvalidInput, e);
+    let mut layout = CaptureLayout::new(args.ant.into(), args.axi_width).map_err(invalid)?;
+    if let Some(chunk) = args.chunk {
+        layout = layout.with_chunk(chunk);
+    }
+    let bytes = std::fs::read(path)?;
+    let capture = layout.decode(&bytes, args.partial);
+
+    println!(
+        "Capture {}: {} samples, of {} radios ({} bytes discarded)\n",
+        path,
+        capture.num_samples(),
+        capture.radios,
+        capture.discarded
+    );
+    print_correlations(&capture.signals(), 1, args.offset);
+    if let Some(vis) = &args.vis {
+        write_visibilities(vis, &capture.signals())?;
+    }
+    if let Some(raw) = &args.raw {
+        write_raw(raw, &capture.signals())?;
+    }
+    Ok(())
 }
 
 fn main() -> std::io::Result<()> {
     let args = Args::parse();
-    println!("Output file {}", args.fname);
+    let bits = if args.fake { 1 } else { args.bits };
 
-    let bits = args.bits;
+    if let Some(prefix) = &args.truth {
+        return write_truth_tables(prefix, bits, args.offset);
+    }
+    if let Some(dir) = &args.golden {
+        return write_golden(dir);
+    }
+    if let Some(path) = &args.capture {
+        return correlate_capture(path, &args);
+    }
+    println!("Output file {}", args.fname);
 
     let mut data: Vec<Vec<DataType>> = Vec::with_capacity(args.ant.into());
 
-    for i in 0..args.ant {
-        println!("Antenna {}", i);
-        let buffer = create_data(args.samples, bits);
-        data.push(buffer); // println!("{:?}", &buffer);
+    if let Some(source) = &args.source {
+        data = create_sky_data(source, &args)?;
+    } else if args.fake {
+        println!(
+            "Fake TART radios, for {} antennas, from cycle {}\n",
+            args.ant, args.skip
+        );
+        data = fake_tart_signals(args.ant.into(), args.samples, args.skip);
+    } else {
+        for i in 0..args.ant {
+            println!("Antenna {}", i);
+            let buffer = create_data(args.samples, bits);
+            data.push(buffer); // println!("{:?}", &buffer);
+        }
     }
 
     // Now write data to a file one set of samples at a time...
     let file = File::create(args.fname).expect("Unable to create file");
     let mut writer = BufWriter::new(file);
 
-    let mut s: Vec<DataType> = vec![Complex::new(0, 0); args.ant.into()];
-
     for i in 0..args.samples {
-        for j in 0..s.capacity() {
-            s[j] = data[j][i];
+        for ant in data.iter() {
+            let s = ant[i];
 
             match bits {
                 1 => {
                     write!(
                         writer,
                         "{:01b}{:01b}",
-                        to_sign_magnitude(s[j].re, bits),
-                        to_sign_magnitude(s[j].im, bits)
+                        to_sign_magnitude(s.re, bits),
+                        to_sign_magnitude(s.im, bits)
                     )?;
                 }
                 2 => {
                     write!(
                         writer,
                         "{:02b}{:02b}",
-                        to_sign_magnitude(s[j].re, bits),
-                        to_sign_magnitude(s[j].im, bits)
+                        to_sign_magnitude(s.re, bits),
+                        to_sign_magnitude(s.im, bits)
                     )?;
                 }
                 _ => println!("Only work with 1-bit or 2-bit sign magnitude data"),
             }
         }
-        write!(writer, "\n")?;
+        writeln!(writer)?;
     }
     writer.flush()?;
 
     // Now generate correlations and write them out
-    for i in 0..args.ant {
-        for j in i..args.ant {
-            let z = correlate(&(data[i as usize]), &(data[j as usize]));
-            println!("{},{} = {:?}", i, j, z);
-        }
+    print_correlations(&data, bits, args.offset);
+    if let Some(vis) = &args.vis {
+        write_visibilities(vis, &data)?;
+    }
+    if let Some(raw) = &args.raw {
+        write_raw(raw, &data)?;
     }
     Ok(())
 }
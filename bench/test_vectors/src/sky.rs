@@ -0,0 +1,134 @@
+use num::complex::Complex;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::correlator::DataType;
+
+/* Sky-model test vectors, where the antenna signals contain a (noise-like)
+ * point source, with the geometric phase of each antenna, plus independent
+ * receiver noise, and are then quantised as by the radio ADCs. So, when the
+ * visibilities are imaged, the source appears at its sky position.
+ *
+ * For a source in the direction 's = (l, m, n)' (east, north, up), antenna 'a'
+ * at position 'r_a' receives the source signal with the phase '2 pi s.r_a / λ',
+ * so that the visibility 'V_ab = <x_a x_b*>' has the phase of the baseline
+ * 'r_a - r_b'.
+ */
+
+pub const SPEED_OF_LIGHT: f64 = 299_792_458.0;
+pub const L1_FREQUENCY: f64 = 1_575.42e6;
+
+/**
+ * Antenna (east, north, up) positions, in metres, in the same (YAML or JSON)
+ * formats as for 'tart-dsp': either a map with 'antenna_positions', or just
+ * the list of positions.
+ */
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PositionsFile {
+    Array { antenna_positions: Vec<[f64; 3]> },
+    Positions(Vec<[f64; 3]>),
+}
+
+pub fn parse_positions(source: &str) -> Result<Vec<[f64; 3]>, String> {
+    let file: PositionsFile = serde_yaml::from_str(source).map_err(|e| e.to_string())?;
+    Ok(match file {
+        PositionsFile::Array { antenna_positions } => antenna_positions,
+        PositionsFile::Positions(positions) => positions,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointSource {
+    pub l: f64,
+    pub m: f64,
+    pub snr: f64,
+}
+
+impl PointSource {
+    /**
+     * Parse the direction-cosines, as 'l,m'.
+     */
+    pub fn parse(text: &str, snr: f64) -> Result<Self, String> {
+        let lm: Vec<f64> = text
+            .split(',')
+            .map(|x| x.trim().parse::<f64>())
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("Invalid source position '{}': {}", text, e))?;
+        match lm[..] {
+            [l, m] if l * l + m * m <= 1.0 => Ok(Self { l, m, snr }),
+            _ => Err(format!(
+                "Source must be 'l,m', within the unit circle: {}",
+                text
+            )),
+        }
+    }
+
+    pub fn n(&self) -> f64 {
+        (1.0 - self.l * self.l - self.m * self.m).max(0.0).sqrt()
+    }
+
+    /**
+     * Geometric phase (in radians) of the source, at the antenna position.
+     */
+    pub fn phase(&self, position: [f64; 3], wavelength: f64) -> f64 {
+        let [e, n, u] = position;
+        2.0 * std::f64::consts::PI * (self.l * e + self.m * n + self.n() * u) / wavelength
+    }
+}
+
+/**
+ * Standard complex Gaussian sample (unit variance for each of Re & Im), using
+ * the Box-Muller transform.
+ */
+fn gaussian(rng: &mut impl Rng) -> Complex<f64> {
+    let u1: f64 = rng.gen_range(f64::MIN_POSITIVE..1.0);
+    let u2: f64 = rng.gen();
+    Complex::from_polar((-2.0 * u1.ln()).sqrt(), 2.0 * std::f64::consts::PI * u2)
+}
+
+/**
+ * Quantise as for the MAX2769 ADC, where the 1-bit values are +/-1, and the
+ * 2-bit magnitude bit is set for about 33% of (Gaussian) samples.
+ */
+fn quantise(x: f64, sigma: f64, bits: u8) -> i32 {
+    let sign = if x < 0.0 { -1 } else { 1 };
+    if bits == 2 && x.abs() >= 0.97 * sigma {
+        sign * 3
+    } else {
+        sign
+    }
+}
+
+/**
+ * Quantised signals of each antenna, for 'samples' samples of the source plus
+ * receiver noise.
+ */
+pub fn antenna_signals(
+    rng: &mut impl Rng,
+    positions: &[[f64; 3]],
+    wavelength: f64,
+    source: &PointSource,
+    samples: usize,
+    bits: u8,
+) -> Vec<Vec<DataType>> {
+    let gain = source.snr.sqrt();
+    let sigma = (1.0 + source.snr).sqrt();
+    let phases: Vec<Complex<f64>> = positions
+        .iter()
+        .map(|&r| Complex::from_polar(1.0, source.phase(r, wavelength)))
+        .collect();
+    let mut signals = vec![Vec::with_capacity(samples); positions.len()];
+
+    for _ in 0..samples {
+        let s = gaussian(rng) * gain;
+        for (signal, &phase) in signals.iter_mut().zip(phases.iter()) {
+            let z = s * phase + gaussian(rng);
+            signal.push(Complex::new(
+                quantise(z.re, sigma, bits),
+                quantise(z.im, sigma, bits),
+            ));
+        }
+    }
+    signals
+}
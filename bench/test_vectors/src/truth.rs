@@ -0,0 +1,221 @@
+use crate::adc::{from_sign_magnitude, max_level, signed_bits, unsigned_bits};
+
+/* Truth-tables for the correlator lookup ROMs, for 1-bit and 2-bit sign/
+ * magnitude data. For the visibility calculation:
+ *
+ *   Z_a = I_a + j Q_a ,  Z_b = I_b + j Q_b ,
+ *   Z_a * conj(Z_b) = (I_a I_b + Q_a Q_b) + j (Q_a I_b - I_a Q_b) ,
+ *
+ * each row of a table is indexed by the address '{I_a, Q_a, I_b, Q_b}', the
+ * same ordering as the 'bits' of the 'correlate' core.
+ */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TruthRow {
+    pub codes: [i32; 4],
+    pub re: i32,
+    pub im: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TruthTable {
+    pub bits: u8,
+    pub offset: bool,
+    pub width: usize,
+    pub rows: Vec<TruthRow>,
+}
+
+impl TruthTable {
+    /**
+     * Enumerate all of the sign/magnitude codes, for 'bits'-bit data. When
+     * 'offset' is set, the outputs are stored as unsigned values, by adding
+     * the largest product magnitude (so two, for 1-bit data) to each value,
+     * instead of using two's-complement.
+     */
+    pub fn new(bits: u8, offset: bool) -> Self {
+        let codes = 1 << bits;
+        let mut rows = Vec::with_capacity(codes * codes * codes * codes);
+
+        for ia in 0..codes as i32 {
+            for qa in 0..codes as i32 {
+                for ib in 0..codes as i32 {
+                    for qb in 0..codes as i32 {
+                        let (xi, xq) =
+                            (from_sign_magnitude(ia, bits), from_sign_magnitude(qa, bits));
+                        let (yi, yq) =
+                            (from_sign_magnitude(ib, bits), from_sign_magnitude(qb, bits));
+                        rows.push(TruthRow {
+                            codes: [ia, qa, ib, qb],
+                            re: xi * yi + xq * yq,
+                            im: xq * yi - xi * yq,
+                        });
+                    }
+                }
+            }
+        }
+
+        let bias = Self::bias_for(bits);
+        let width = if offset {
+            unsigned_bits(bias << 1)
+        } else {
+            signed_bits(bias)
+        };
+
+        Self {
+            bits,
+            offset,
+            width,
+            rows,
+        }
+    }
+
+    /**
+     * Largest magnitude of each (real, or imaginary) product, and this is also
+     * the value added to each product, when using offset-binary outputs.
+     */
+    pub fn bias_for(bits: u8) -> i32 {
+        let level = max_level(bits);
+        2 * level * level
+    }
+
+    pub fn bias(&self) -> i32 {
+        if self.offset {
+            Self::bias_for(self.bits)
+        } else {
+            0
+        }
+    }
+
+    /**
+     * Stored (ROM) value for the given product, as a 'width'-bit word.
+     */
+    pub fn encode(&self, value: i32) -> u32 {
+        let mask = (1u32 << self.width) - 1;
+        (value + self.bias()) as u32 & mask
+    }
+
+    pub fn address_bits(&self) -> usize {
+        4 * self.bits as usize
+    }
+
+    fn code_string(&self, code: i32) -> String {
+        format!("{:0w$b}", code, w = self.bits as usize)
+    }
+
+    fn word_string(&self, value: i32) -> String {
+        format!("{:0w$b}", self.encode(value), w = self.width)
+    }
+
+    /**
+     * Markdown table, in the same layout as the correlator README.
+     */
+    pub fn to_markdown(&self) -> String {
+        let mut out = Vec::with_capacity(self.rows.len() + 4);
+        out.push(
+            "| $I_a$ | $Q_a$ | $I_b$ | $Q_b$ |   | $\\mathcal{R}_{ab}$ |    \
+             | $\\mathcal{I}_{ab}$ |    |"
+                .to_string(),
+        );
+        out.push(
+            "|:-----:|:-----:|:-----:|:-----:|---|:------------------:|:--:\
+             |:------------------:|:--:|"
+                .to_string(),
+        );
+
+        for row in self.rows.iter() {
+            let [ia, qa, ib, qb] = row.codes;
+            out.push(format!(
+                "| {:5} | {:5} | {:5} | {:5} |   | {:18} | {:<2} \
+                 | {:18} | {:<2} |",
+                self.code_string(ia),
+                self.code_string(qa),
+                self.code_string(ib),
+                self.code_string(qb),
+                format!("0b{}", self.word_string(row.re)),
+                row.re,
+                format!("0b{}", self.word_string(row.im)),
+                row.im
+            ));
+        }
+
+        out.push(String::new());
+        if self.offset {
+            out.push(format!(
+                "where the $\\mathcal{{R,I}}$ columns include {}-bit, \
+                 offset-binary values (with an offset of {}), as well as \
+                 the base-10 products.",
+                self.width,
+                self.bias()
+            ));
+        } else {
+            out.push(format!(
+                "where the $\\mathcal{{R,I}}$ columns include {}-bit, \
+                 twos-complement (binary) values, as well as base-10.",
+                self.width
+            ));
+        }
+        out.join("\n") + "\n"
+    }
+
+    /**
+     * Verilog 'case' statement that assigns 're' and 'im', from the concate-
+     * nated input codes.
+     */
+    pub fn to_verilog(&self) -> String {
+        let abits = self.address_bits();
+        let mut out = Vec::with_capacity(self.rows.len() + 4);
+        out.push(format!(
+            "// {}-bit correlator truth-table, with {}-bit {} outputs",
+            self.bits,
+            self.width,
+            if self.offset {
+                "offset-binary"
+            } else {
+                "two's-complement"
+            }
+        ));
+        out.push("case ({ai, aq, bi, bq})".to_string());
+
+        for (addr, row) in self.rows.iter().enumerate() {
+            out.push(format!(
+                "  {}'b{:0a$b}: {{re, im}} = {{{}'b{}, {}'b{}}};",
+                abits,
+                addr,
+                self.width,
+                self.word_string(row.re),
+                self.width,
+                self.word_string(row.im),
+                a = abits
+            ));
+        }
+
+        out.push(format!(
+            "  default: {{re, im}} = {{{}{{1'bx}}}};",
+            self.width << 1
+        ));
+        out.push("endcase".to_string());
+        out.join("\n") + "\n"
+    }
+
+    /**
+     * ROM image for '$readmemb', with one '{re, im}' word per address.
+     */
+    pub fn to_readmemb(&self) -> String {
+        let mut out = Vec::with_capacity(self.rows.len() + 1);
+        out.push(format!(
+            "// {}-bit correlator ROM, {} x {{re[{}:0], im[{}:0]}}",
+            self.bits,
+            self.rows.len(),
+            self.width - 1,
+            self.width - 1
+        ));
+        for row in self.rows.iter() {
+            out.push(format!(
+                "{}{}",
+                self.word_string(row.re),
+                self.word_string(row.im)
+            ));
+        }
+        out.join("\n") + "\n"
+    }
+}
@@ -0,0 +1,368 @@
+use std::collections::HashMap;
+use std::fmt;
+
+/* Reader for the Value Change Dump (VCD) files written by the testbenches, so
+ * that the signals of a simulation can be sampled on a clock-edge, and then
+ * compared against the reference model.
+ */
+
+/**
+ * The 4-state value of a signal, with the MSB first.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Value(pub String);
+
+impl Value {
+    /**
+     * Extend to 'width' bits, as for VCD vector values, where a leading '0' or
+     * '1' is zero-extended, and a leading 'x' or 'z' is extended with itself.
+     */
+    fn extend(bits: &str, width: usize) -> Self {
+        let bits = bits.to_lowercase();
+        if bits.len() >= width {
+            return Self(bits[bits.len() - width..].to_string());
+        }
+        let fill = match bits.chars().next() {
+            Some('x') => 'x',
+            Some('z') => 'z',
+            _ => '0',
+        };
+        let mut s: String = std::iter::repeat_n(fill, width - bits.len()).collect();
+        s.push_str(&bits);
+        Self(s)
+    }
+
+    pub fn unknown(width: usize) -> Self {
+        Self("x".repeat(width.max(1)))
+    }
+
+    pub fn width(&self) -> usize {
+        self.0.len()
+    }
+
+    /**
+     * The (unsigned) value, if no bits are 'x' or 'z'.
+     */
+    pub fn to_u64(&self) -> Option<u64> {
+        self.0.chars().try_fold(0u64, |acc, c| match c {
+            '0' => Some(acc << 1),
+            '1' => Some(acc << 1 | 1),
+            _ => None,
+        })
+    }
+
+    pub fn is_high(&self) -> bool {
+        self.to_u64().is_some_and(|x| x != 0)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.to_u64() {
+            Some(x) => write!(f, "{:x}", x),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+/**
+ * A declared variable, with its hierarchical name, e.g. 'tart_correlator_tb.
+ * TARTCOR0.bus_revis_o'.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signal {
+    pub code: String,
+    pub name: String,
+    pub scope: String,
+    pub width: usize,
+}
+
+impl Signal {
+    pub fn path(&self) -> String {
+        if self.scope.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}.{}", self.scope, self.name)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    Rising,
+    Falling,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Vcd {
+    pub timescale: String,
+    pub signals: Vec<Signal>,
+    changes: HashMap<String, Vec<(u64, Value)>>,
+}
+
+impl Vcd {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut vcd = Vcd::default();
+        let mut widths: HashMap<String, usize> = HashMap::new();
+        let mut scopes: Vec<String> = Vec::new();
+        let mut tokens = source.split_whitespace();
+        let mut time = 0u64;
+        let mut header = true;
+
+        while let Some(token) = tokens.next() {
+            if header {
+                match token {
+                    "$scope" => {
+                        let _kind = tokens.next();
+                        scopes.push(tokens.next().unwrap_or("").to_string());
+                        skip_to_end(&mut tokens);
+                    }
+                    "$upscope" => {
+                        scopes.pop();
+                        skip_to_end(&mut tokens);
+                    }
+                    "$timescale" => {
+                        let words: Vec<&str> =
+                            tokens.by_ref().take_while(|&t| t != "$end").collect();
+                        vcd.timescale = words.concat();
+                    }
+                    "$var" => {
+                        let words: Vec<&str> =
+                            tokens.by_ref().take_while(|&t| t != "$end").collect();
+                        if words.len() < 4 {
+                            return Err(format!("Invalid '$var': {:?}", words));
+                        }
+                        let width = words[1]
+                            .parse()
+                            .map_err(|_| format!("Invalid '$var' width: {}", words[1]))?;
+                        vcd.signals.push(Signal {
+                            code: words[2].to_string(),
+                            name: words[3].to_string(),
+                            scope: scopes.join("."),
+                            width,
+                        });
+                    }
+                    "$enddefinitions" => {
+                        skip_to_end(&mut tokens);
+                        for s in vcd.signals.iter() {
+                            widths.insert(s.code.clone(), s.width);
+                        }
+                        header = false;
+                    }
+                    _ if token.starts_with('$') => skip_to_end(&mut tokens),
+                    _ => return Err(format!("Unexpected token in header: {}", token)),
+                }
+                continue;
+            }
+
+            let first = token.chars().next().unwrap_or(' ');
+            match first {
+                '#' => {
+                    time = token[1..]
+                        .parse()
+                        .map_err(|_| format!("Invalid time: {}", token))?;
+                }
+                '$' => {
+                    // '$dumpvars', '$dumpon', etc., contain value changes, so
+                    // only their '$end' markers are dropped
+                }
+                'b' | 'B' => {
+                    let code = tokens
+                        .next()
+                        .ok_or(format!("Missing identifier for: {}", token))?;
+                    let width = widths.get(code).copied();
+                    vcd.change(time, code, &token[1..], width);
+                }
+                'r' | 'R' => {
+                    // Real-valued variables are not supported
+                    tokens.next();
+                }
+                '0' | '1' | 'x' | 'X' | 'z' | 'Z' => {
+                    let width = widths.get(&token[1..]).copied();
+                    vcd.change(time, &token[1..], &token[..1], width);
+                }
+                _ => return Err(format!("Unexpected value-change: {}", token)),
+            }
+        }
+        Ok(vcd)
+    }
+
+    fn change(&mut self, time: u64, code: &str, bits: &str, width: Option<usize>) {
+        let width = width.unwrap_or(bits.len());
+        self.changes
+            .entry(code.to_string())
+            .or_default()
+            .push((time, Value::extend(bits, width)));
+    }
+
+    /**
+     * Find a signal by its full hierarchical name, or else by the shortest
+     * path ending with the given name (so 'bus_revis_o' finds the testbench
+     * signal before those within sub-modules).
+     */
+    pub fn find(&self, name: &str) -> Option<&Signal> {
+        if let Some(s) = self.signals.iter().find(|s| s.path() == name) {
+            return Some(s);
+        }
+        let suffix = format!(".{}", name);
+        self.signals
+            .iter()
+            .filter(|s| s.name == name || s.path().ends_with(&suffix))
+            .min_by_key(|s| s.path().len())
+    }
+
+    /**
+     * Value of the signal just before 'time', so that values that change at a
+     * clock-edge are those that were sampled by that edge.
+     */
+    pub fn value_before(&self, signal: &Signal, time: u64) -> Value {
+        let changes = match self.changes.get(&signal.code) {
+            Some(changes) => changes,
+            None => return Value::unknown(signal.width),
+        };
+        let index = changes.partition_point(|(t, _)| *t < time);
+        if index == 0 {
+            Value::unknown(signal.width)
+        } else {
+            changes[index - 1].1.clone()
+        }
+    }
+
+    /**
+     * Times of each of the given edges of the (1-bit) clock signal.
+     */
+    pub fn edges(&self, clock: &Signal, edge: Edge) -> Vec<u64> {
+        let want = match edge {
+            Edge::Rising => "1",
+            Edge::Falling => "0",
+        };
+        let mut prev = String::from("x");
+        let mut times = Vec::new();
+        for (t, v) in self.changes.get(&clock.code).into_iter().flatten() {
+            if v.0 == want && prev != want && prev != "x" {
+                times.push(*t);
+            }
+            prev = v.0.clone();
+        }
+        times
+    }
+
+    /**
+     * Sample each of the signals, on every edge of the clock.
+     */
+    pub fn sample(&self, clock: &Signal, edge: Edge, signals: &[&Signal]) -> Vec<Sample> {
+        self.edges(clock, edge)
+            .into_iter()
+            .enumerate()
+            .map(|(cycle, time)| Sample {
+                cycle,
+                time,
+                values: signals.iter().map(|s| self.value_before(s, time)).collect(),
+            })
+            .collect()
+    }
+}
+
+fn skip_to_end<'a>(tokens: &mut impl Iterator<Item = &'a str>) {
+    for t in tokens.by_ref() {
+        if t == "$end" {
+            break;
+        }
+    }
+}
+
+/**
+ * The values of the sampled signals, at a clock-edge.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sample {
+    pub cycle: usize,
+    pub time: u64,
+    pub values: Vec<Value>,
+}
+
+/**
+ * The first (valid) sample that differs from the expected values, along with
+ * the index of this output, and where a missing output has no sample.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub index: usize,
+    pub sample: Option<Sample>,
+    pub expected: Vec<u64>,
+}
+
+/**
+ * Compare the (valid) samples against the expected values, in order, where
+ * each expected row has one value for each sampled signal. Returns the first
+ * mismatch, or 'None' if all of the expected values were matched (ignoring any
+ * additional samples).
+ */
+pub fn compare(samples: &[&Sample], expected: &[Vec<u64>]) -> Option<Mismatch> {
+    for (index, exp) in expected.iter().enumerate() {
+        let sample = match samples.get(index) {
+            Some(sample) => sample,
+            None => {
+                return Some(Mismatch {
+                    index,
+                    sample: None,
+                    expected: exp.clone(),
+                })
+            }
+        };
+        let matches = sample
+            .values
+            .iter()
+            .zip(exp.iter())
+            .all(|(v, &x)| v.to_u64() == Some(x));
+        if !matches {
+            return Some(Mismatch {
+                index,
+                sample: Some((*sample).clone()),
+                expected: exp.clone(),
+            });
+        }
+    }
+    None
+}
+
+/**
+ * Parse the words of a '$readmemh' file, skipping comments and any address
+ * markers.
+ */
+pub fn read_memh(source: &str) -> Result<Vec<u64>, String> {
+    let mut words = Vec::new();
+    for line in source.lines() {
+        let line = line.split("//").next().unwrap_or("");
+        for token in line.split_whitespace() {
+            if token.starts_with('@') {
+                continue;
+            }
+            let word = u64::from_str_radix(&token.replace('_', ""), 16)
+                .map_err(|_| format!("Invalid '$readmemh' word: {}", token))?;
+            words.push(word);
+        }
+    }
+    Ok(words)
+}
+
+/**
+ * Split each packed word into one value per field, with the first field in
+ * the MSBs (as for a Verilog concatenation).
+ */
+pub fn split_words(words: &[u64], widths: &[usize]) -> Vec<Vec<u64>> {
+    words
+        .iter()
+        .map(|&word| {
+            let mut shift: usize = widths.iter().sum();
+            widths
+                .iter()
+                .map(|&w| {
+                    shift -= w;
+                    let mask = if w >= 64 { u64::MAX } else { (1 << w) - 1 };
+                    (word >> shift) & mask
+                })
+                .collect()
+        })
+        .collect()
+}
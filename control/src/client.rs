@@ -0,0 +1,76 @@
+use std::io;
+
+use crate::protocol::Command;
+use crate::registers::{ExtraFlags, RegAddr, Register, Reset, Status};
+use crate::transport::Transport;
+
+/**
+ * Host-side client for the controller, using any transport.
+ */
+pub struct Controller<T: Transport> {
+    transport: T,
+}
+
+impl<T: Transport> Controller<T> {
+    pub fn new(transport: T) -> Self {
+        Self { transport }
+    }
+
+    pub fn transport(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    /**
+     * Send the command, and then wait for any response bytes.
+     */
+    pub fn execute(&mut self, command: Command) -> io::Result<Vec<u8>> {
+        self.transport.send(&command.encode())?;
+        let mut response = vec![0; command.response_len()];
+        self.transport.receive(&mut response)?;
+        Ok(response)
+    }
+
+    pub fn read_register(&mut self, reg: RegAddr) -> io::Result<u8> {
+        let response = self.execute(Command::read(reg))?;
+        Ok(response[0])
+    }
+
+    pub fn write_register(
+        &mut self,
+        reg: RegAddr,
+        value: u8,
+    ) -> io::Result<()> {
+        if !reg.is_writable() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Register {:?} is read-only", reg),
+            ));
+        }
+        self.execute(Command::write(reg, value))?;
+        Ok(())
+    }
+
+    pub fn read<R: Register>(&mut self) -> io::Result<R> {
+        Ok(R::from_byte(self.read_register(R::ADDRESS)?))
+    }
+
+    pub fn status(&mut self) -> io::Result<Status> {
+        self.read()
+    }
+
+    pub fn extra_flags(&mut self) -> io::Result<ExtraFlags> {
+        self.read()
+    }
+
+    pub fn set_reset(&mut self, reset: bool) -> io::Result<()> {
+        self.write_register(RegAddr::Reset, Reset { reset }.to_byte())
+    }
+
+    /**
+     * Pulse the reset, to restart the capture and acquisition units.
+     */
+    pub fn reset(&mut self) -> io::Result<()> {
+        self.set_reset(true)?;
+        self.set_reset(false)
+    }
+}
@@ -0,0 +1,9 @@
+pub use crate::client::*;
+pub use crate::protocol::*;
+pub use crate::registers::*;
+pub use crate::transport::*;
+
+pub mod client;
+pub mod protocol;
+pub mod registers;
+pub mod transport;
@@ -0,0 +1,71 @@
+use clap::Parser;
+use std::time::Duration;
+use tart_control::{
+    connect_tcp, open_serial, Controller, MockController, MockTransport,
+    RegAddr, Transport,
+};
+
+/// Reads and writes the TART controller registers, over the USB/UART serial-
+/// port, a TCP bridge to the byte-stream, or an in-process model of the
+/// controller (for testing host software without hardware).
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Serial-port device of the USB/UART
+    #[arg(short, long, value_name = "PORT")]
+    serial: Option<String>,
+
+    /// Baud-rate of the serial-port
+    #[arg(short, long, value_name = "BAUD", default_value = "115200")]
+    baud: u32,
+
+    /// TCP address of a bridge to the byte-stream (e.g., 'localhost:2000')
+    #[arg(short, long, value_name = "ADDR")]
+    tcp: Option<String>,
+
+    /// Response timeout, in milliseconds
+    #[arg(long, value_name = "MS", default_value = "500")]
+    timeout: u64,
+
+    /// Pulse the reset, before reading the registers
+    #[arg(short, long)]
+    reset: bool,
+}
+
+fn run<T: Transport>(
+    mut controller: Controller<T>,
+    args: &Args,
+) -> std::io::Result<()> {
+    if args.reset {
+        controller.reset()?;
+        println!("Reset pulsed");
+    }
+    println!("{}", controller.status()?);
+    println!("{}", controller.extra_flags()?);
+    println!(
+        "Reserved: 0x{:02x}",
+        controller.read_register(RegAddr::Reserved)?
+    );
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Args = Args::parse();
+    let timeout = Duration::from_millis(args.timeout);
+
+    if let Some(path) = &args.serial {
+        run(
+            Controller::new(open_serial(path, args.baud, timeout)?),
+            &args,
+        )?;
+    } else if let Some(addr) = &args.tcp {
+        run(Controller::new(connect_tcp(addr, timeout)?), &args)?;
+    } else {
+        println!(
+            "No serial-port, or TCP address, so using the mock controller\n"
+        );
+        let mock = MockTransport::new(MockController::new());
+        run(Controller::new(mock), &args)?;
+    }
+    Ok(())
+}
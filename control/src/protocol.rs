@@ -0,0 +1,127 @@
+use crate::registers::RegAddr;
+
+/* Proposed byte protocol of the controller, carried by the 8-bit AXI4-Stream
+ * from the USB/UART ('s_tdata'), with the responses returned by the 'm_tdata'
+ * stream.
+ *
+ * NOTE: this protocol is assumed, and is not (yet) implemented by the RTL, as
+ * 'rtl/tart/controller.v' has no command decoder, and its 'm_tdata' output is
+ * just the visibilities stream ('v_tdata').
+ *
+ * Each command starts with the byte '{WRITE, ADDR[6:0]}', and:
+ *  - a read (WRITE = 0) returns the one-byte value of the register; and
+ *  - a write (WRITE = 1) is followed by the new (one-byte) register value,
+ *    and has no response.
+ *
+ * The 's_tlast' marker is asserted with the final byte of each command, and
+ * 'm_tlast' with the final byte of each response.
+ */
+
+/// Base address of the controller registers.
+pub const CONTROL_BASE: u8 = 0x00;
+
+/// Base address of the DSP/visibilities unit, '7'b100_00xx'.
+pub const DSP_BASE: u8 = 0x40;
+
+pub const WRITE_FLAG: u8 = 0x80;
+pub const ADDR_MASK: u8 = 0x7f;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Read { addr: u8 },
+    Write { addr: u8, value: u8 },
+}
+
+impl Command {
+    pub fn read(reg: RegAddr) -> Self {
+        Self::Read {
+            addr: CONTROL_BASE | reg.index(),
+        }
+    }
+
+    pub fn write(reg: RegAddr, value: u8) -> Self {
+        Self::Write {
+            addr: CONTROL_BASE | reg.index(),
+            value,
+        }
+    }
+
+    pub fn addr(&self) -> u8 {
+        match *self {
+            Self::Read { addr } | Self::Write { addr, .. } => addr,
+        }
+    }
+
+    /**
+     * Controller register addressed by the command, if any.
+     */
+    pub fn register(&self) -> Option<RegAddr> {
+        let addr = self.addr();
+        if addr & !0x03 == CONTROL_BASE {
+            RegAddr::from_index(addr)
+        } else {
+            None
+        }
+    }
+
+    /**
+     * Number of response bytes, that are returned by the controller.
+     */
+    pub fn response_len(&self) -> usize {
+        match self {
+            Self::Read { .. } => 1,
+            Self::Write { .. } => 0,
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        match *self {
+            Self::Read { addr } => vec![addr & ADDR_MASK],
+            Self::Write { addr, value } => {
+                vec![WRITE_FLAG | (addr & ADDR_MASK), value]
+            }
+        }
+    }
+
+    /**
+     * Encoded command, with the 'tlast' marker for each byte.
+     */
+    pub fn encode_beats(&self) -> Vec<(u8, bool)> {
+        let bytes = self.encode();
+        let n = bytes.len();
+        bytes
+            .into_iter()
+            .enumerate()
+            .map(|(i, b)| (b, i + 1 == n))
+            .collect()
+    }
+
+    /**
+     * Decode the first command from the bytes, returning the command and the
+     * number of bytes used, or 'None' if more bytes are required.
+     */
+    pub fn decode(bytes: &[u8]) -> Option<(Self, usize)> {
+        let &first = bytes.first()?;
+        let addr = first & ADDR_MASK;
+
+        if first & WRITE_FLAG == 0 {
+            Some((Self::Read { addr }, 1))
+        } else {
+            let &value = bytes.get(1)?;
+            Some((Self::Write { addr, value }, 2))
+        }
+    }
+
+    /**
+     * Decode all complete commands, returning them and any trailing bytes.
+     */
+    pub fn decode_all(bytes: &[u8]) -> (Vec<Self>, &[u8]) {
+        let mut commands = Vec::new();
+        let mut rest = bytes;
+        while let Some((command, used)) = Self::decode(rest) {
+            commands.push(command);
+            rest = &rest[used..];
+        }
+        (commands, rest)
+    }
+}
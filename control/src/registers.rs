@@ -0,0 +1,198 @@
+use std::fmt;
+
+/**
+ * Register-map of the TART controller (`rtl/tart/controller.v`):
+ *
+ *   2'b00  --  status register;
+ *   2'b01  --  extra status-flags;
+ *   2'b10  --  reserved/miscellaneous register; and
+ *   2'b11  --  reset register.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegAddr {
+    Status = 0,
+    Extra = 1,
+    Reserved = 2,
+    Reset = 3,
+}
+
+impl RegAddr {
+    pub fn from_index(index: u8) -> Option<Self> {
+        match index & 0x03 {
+            0 => Some(Self::Status),
+            1 => Some(Self::Extra),
+            2 => Some(Self::Reserved),
+            3 => Some(Self::Reset),
+            _ => None,
+        }
+    }
+
+    pub fn index(&self) -> u8 {
+        *self as u8
+    }
+
+    /**
+     *  Only the reset register can be written, and writes to the others are
+     *  ignored by the controller.
+     */
+    pub fn is_writable(&self) -> bool {
+        matches!(self, Self::Reset)
+    }
+}
+
+/**
+ * Conversion between the typed bit-fields, and the raw (8-bit) register.
+ */
+pub trait Register: Sized {
+    const ADDRESS: RegAddr;
+
+    fn from_byte(byte: u8) -> Self;
+    fn to_byte(&self) -> u8;
+}
+
+fn bit(byte: u8, index: u8) -> bool {
+    (byte >> index) & 1 == 1
+}
+
+/**
+ * Status register (read-only):
+ *
+ * ```text
+ *     7          6          5        4       3      2    1    0
+ *  |  VIZ_EN  | PENDING  | CAP_EN | DEBUG | AQ_EN |    AQ_STATE    |
+ * ```
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Status {
+    pub viz_en: bool,
+    pub pending: bool,
+    pub cap_en: bool,
+    pub debug: bool,
+    pub aq_en: bool,
+    pub aq_state: u8,
+}
+
+impl Register for Status {
+    const ADDRESS: RegAddr = RegAddr::Status;
+
+    fn from_byte(byte: u8) -> Self {
+        Self {
+            viz_en: bit(byte, 7),
+            pending: bit(byte, 6),
+            cap_en: bit(byte, 5),
+            debug: bit(byte, 4),
+            aq_en: bit(byte, 3),
+            aq_state: byte & 0x07,
+        }
+    }
+
+    fn to_byte(&self) -> u8 {
+        (self.viz_en as u8) << 7
+            | (self.pending as u8) << 6
+            | (self.cap_en as u8) << 5
+            | (self.debug as u8) << 4
+            | (self.aq_en as u8) << 3
+            | self.aq_state & 0x07
+    }
+}
+
+/**
+ * Extra status-flags register (read-only):
+ *
+ * ```text
+ *     7          6          5    4    3    2    1      0
+ *  | OVERFLOW | UNDERRUN |         5'h00           | BUSY  |
+ * ```
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExtraFlags {
+    pub overflow: bool,
+    pub underrun: bool,
+    pub busy: bool,
+}
+
+impl Register for ExtraFlags {
+    const ADDRESS: RegAddr = RegAddr::Extra;
+
+    fn from_byte(byte: u8) -> Self {
+        Self {
+            overflow: bit(byte, 7),
+            underrun: bit(byte, 6),
+            busy: bit(byte, 0),
+        }
+    }
+
+    fn to_byte(&self) -> u8 {
+        (self.overflow as u8) << 7
+            | (self.underrun as u8) << 6
+            | self.busy as u8
+    }
+}
+
+/**
+ * Reserved/miscellaneous register, with no defined bit-fields.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Reserved(pub u8);
+
+impl Register for Reserved {
+    const ADDRESS: RegAddr = RegAddr::Reserved;
+
+    fn from_byte(byte: u8) -> Self {
+        Self(byte)
+    }
+
+    fn to_byte(&self) -> u8 {
+        self.0
+    }
+}
+
+/**
+ * Reset register (read/write):
+ *
+ * ```text
+ *     7    6    5    4    3    2    1      0
+ *  |              7'h00               | RESET |
+ * ```
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Reset {
+    pub reset: bool,
+}
+
+impl Register for Reset {
+    const ADDRESS: RegAddr = RegAddr::Reset;
+
+    fn from_byte(byte: u8) -> Self {
+        Self {
+            reset: bit(byte, 0),
+        }
+    }
+
+    fn to_byte(&self) -> u8 {
+        self.reset as u8
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Status {{")?;
+        writeln!(f, "    viz_en: {}", self.viz_en)?;
+        writeln!(f, "    pending: {}", self.pending)?;
+        writeln!(f, "    cap_en: {}", self.cap_en)?;
+        writeln!(f, "    debug: {}", self.debug)?;
+        writeln!(f, "    aq_en: {}", self.aq_en)?;
+        writeln!(f, "    aq_state: {}", self.aq_state)?;
+        writeln!(f, "}}")
+    }
+}
+
+impl fmt::Display for ExtraFlags {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "ExtraFlags {{")?;
+        writeln!(f, "    overflow: {}", self.overflow)?;
+        writeln!(f, "    underrun: {}", self.underrun)?;
+        writeln!(f, "    busy: {}", self.busy)?;
+        writeln!(f, "}}")
+    }
+}
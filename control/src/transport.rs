@@ -0,0 +1,197 @@
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use crate::protocol::Command;
+use crate::registers::{ExtraFlags, RegAddr, Register, Reset, Status};
+
+/**
+ * Byte-stream link to the controller, for sending commands and receiving the
+ * responses.
+ */
+pub trait Transport {
+    fn send(&mut self, bytes: &[u8]) -> io::Result<()>;
+
+    /**
+     * Fill the buffer with response bytes, or fail (e.g., after a timeout).
+     */
+    fn receive(&mut self, buffer: &mut [u8]) -> io::Result<()>;
+}
+
+/**
+ * Transport over any (blocking) byte-stream, such as a serial-port or a TCP
+ * socket.
+ */
+pub struct StreamTransport<S: Read + Write> {
+    stream: S,
+}
+
+impl<S: Read + Write> StreamTransport<S> {
+    pub fn new(stream: S) -> Self {
+        Self { stream }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S: Read + Write> Transport for StreamTransport<S> {
+    fn send(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.stream.write_all(bytes)?;
+        self.stream.flush()
+    }
+
+    fn receive(&mut self, buffer: &mut [u8]) -> io::Result<()> {
+        self.stream.read_exact(buffer)
+    }
+}
+
+pub type SerialTransport = StreamTransport<Box<dyn serialport::SerialPort>>;
+pub type TcpTransport = StreamTransport<TcpStream>;
+
+/**
+ * Open the USB/UART serial-port, with the given baud-rate.
+ */
+pub fn open_serial(
+    path: &str,
+    baud: u32,
+    timeout: Duration,
+) -> io::Result<SerialTransport> {
+    let port = serialport::new(path, baud).timeout(timeout).open()?;
+    Ok(StreamTransport::new(port))
+}
+
+/**
+ * Connect to a TCP bridge (e.g., 'ser2net', or a simulator) for the byte-
+ * stream.
+ */
+pub fn connect_tcp<A: ToSocketAddrs>(
+    addr: A,
+    timeout: Duration,
+) -> io::Result<TcpTransport> {
+    let stream = TcpStream::connect(addr)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_nodelay(true)?;
+    Ok(StreamTransport::new(stream))
+}
+
+/**
+ * In-process model of the controller registers, that responds to commands
+ * using the (proposed) byte protocol of 'protocol.rs', so that host software
+ * can be run without a TART.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MockController {
+    pub status: Status,
+    pub extra: ExtraFlags,
+    pub reserved: u8,
+    pub reset: Reset,
+}
+
+impl MockController {
+    /**
+     * Controller state after power-on, with capture enabled (as the hardware
+     * does), and acquisition waiting for the DDR3.
+     */
+    pub fn new() -> Self {
+        Self {
+            status: Status {
+                cap_en: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    pub fn read(&self, reg: RegAddr) -> u8 {
+        match reg {
+            RegAddr::Status => self.status.to_byte(),
+            RegAddr::Extra => self.extra.to_byte(),
+            RegAddr::Reserved => self.reserved,
+            RegAddr::Reset => self.reset.to_byte(),
+        }
+    }
+
+    /**
+     * Writes to read-only registers are ignored, and asserting 'RESET' stops
+     * the capture, acquisition, and correlators, and clears the flags.
+     */
+    pub fn write(&mut self, reg: RegAddr, value: u8) {
+        if reg != RegAddr::Reset {
+            return;
+        }
+        self.reset = Reset::from_byte(value);
+        if self.reset.reset {
+            self.status = Status::default();
+            self.extra = ExtraFlags::default();
+        } else {
+            self.status.cap_en = true;
+        }
+    }
+
+    /**
+     * Execute the command, and return its response bytes. Unmapped addresses
+     * read as zero.
+     */
+    pub fn execute(&mut self, command: Command) -> Vec<u8> {
+        match (command, command.register()) {
+            (Command::Read { .. }, Some(reg)) => vec![self.read(reg)],
+            (Command::Read { .. }, None) => vec![0x00],
+            (Command::Write { value, .. }, Some(reg)) => {
+                self.write(reg, value);
+                Vec::new()
+            }
+            (Command::Write { .. }, None) => Vec::new(),
+        }
+    }
+}
+
+/**
+ * Loopback transport, to the in-process controller model.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct MockTransport {
+    pub controller: MockController,
+    pending: Vec<u8>,
+    responses: VecDeque<u8>,
+}
+
+impl MockTransport {
+    pub fn new(controller: MockController) -> Self {
+        Self {
+            controller,
+            pending: Vec::new(),
+            responses: VecDeque::new(),
+        }
+    }
+}
+
+impl Transport for MockTransport {
+    fn send(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.pending.extend_from_slice(bytes);
+        let (commands, rest) = Command::decode_all(&self.pending);
+        let used = self.pending.len() - rest.len();
+
+        for command in commands {
+            let response = self.controller.execute(command);
+            self.responses.extend(response);
+        }
+        self.pending.drain(..used);
+        Ok(())
+    }
+
+    fn receive(&mut self, buffer: &mut [u8]) -> io::Result<()> {
+        if buffer.len() > self.responses.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "No response from the (mock) controller",
+            ));
+        }
+        for b in buffer.iter_mut() {
+            *b = self.responses.pop_front().unwrap();
+        }
+        Ok(())
+    }
+}
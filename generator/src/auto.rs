@@ -0,0 +1,243 @@
+use log::{info, warn};
+use std::fmt;
+
+use crate::context::Context;
+use crate::estimate::{CostTable, Estimate};
+use crate::refine::Replacement;
+use crate::solution::Solution;
+use crate::widths::BitWidths;
+
+/**
+ * Each of the changes that can be made, to the derived configuration, so that
+ * the visibility calculations can be assigned.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Escalation {
+    Refinement(Vec<Replacement>),
+    MuxWidth { from: usize, to: usize },
+    Units { from: usize, to: usize },
+}
+
+impl fmt::Display for Escalation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Refinement(moves) => {
+                write!(f, "refinement ({} replacements)", moves.len())
+            }
+            Self::MuxWidth { from, to } => {
+                write!(f, "MUX-width {} -> {}", from, to)
+            }
+            Self::Units { from, to } => {
+                write!(f, "correlator units {} -> {}", from, to)
+            }
+        }
+    }
+}
+
+/**
+ * Automatic solve, which starts with the configuration derived by
+ * 'Context::new' (or 'Context::with_edges'), along with any dead antennas and
+ * disabled units, and then escalates until the first complete solution:
+ *  1) refines the partition, by replacing MUX inputs;
+ *  2) widens the MUXs, one input at a time, up to 'num_antennas / 2'; and
+ *  3) adds correlator units, one at a time, with the derived MUX-width, up to
+ *     twice the derived number of units.
+ *
+ * Each configuration is also refined, if its first partition is incomplete.
+ */
+#[derive(Debug, Clone)]
+pub struct AutoSolve {
+    pub initial: Context,
+    pub escalations: Vec<Escalation>,
+    pub solution: Option<Solution>,
+}
+
+/**
+ * Solve the (partitioned) context, and then check that the solution computes
+ * every visibility and signal-mean.
+ */
+fn try_solve(context: &Context) -> Option<Solution> {
+    if !context.is_complete() {
+        return None;
+    }
+    Solution::solve(&mut context.clone()).filter(|s| s.is_complete())
+}
+
+/**
+ * Partition and then solve the context, refining the partition if needed.
+ */
+fn try_partition(
+    context: &mut Context,
+) -> (Option<Solution>, Option<Escalation>) {
+    context.partition(false);
+    if let Some(solution) = try_solve(context) {
+        return (Some(solution), None);
+    }
+    let moves = context.refine();
+    if moves.is_empty() {
+        return (None, None);
+    }
+    (try_solve(context), Some(Escalation::Refinement(moves)))
+}
+
+/**
+ * Copy of the context, with the same edges, masks, and settings, but with the
+ * MUX-width derived for the given number of extra MUX-width inputs, unless
+ * this is too wide for the (live) antennas.
+ */
+fn derive(context: &Context, extra_bits: usize) -> Option<Context> {
+    let width = Context::with_edges(
+        context.num_antennas,
+        &context.edges_array,
+        context.clock_multiplier,
+        context.no_means,
+        extra_bits,
+    )
+    .expect("Edges of the context are valid")
+    .mux_width;
+    let mut derived = context.clone();
+    derived.set_mux_widths(width, width).ok()?;
+    Some(derived)
+}
+
+impl AutoSolve {
+    /**
+     *  Escalates from the context, which has the configuration derived (with
+     *  'extra_bits') for its edges.
+     */
+    pub fn new(context: Context, extra_bits: usize) -> Self {
+        let (num_units, mux_width) = (context.num_units, context.mux_width);
+        let mut auto = Self {
+            initial: context.clone(),
+            escalations: Vec::new(),
+            solution: None,
+        };
+
+        // Derived configuration, and then with refinement
+        let mut context = context;
+        let (solution, refined) = try_partition(&mut context);
+        auto.initial = context;
+        if auto.finish(solution, refined) {
+            return auto;
+        }
+
+        // Wider MUXs
+        let mut width = mux_width;
+        for extra in extra_bits + 1.. {
+            let Some(mut context) = derive(&auto.initial, extra) else {
+                break;
+            };
+            if context.mux_width <= width {
+                break;
+            }
+            width = context.mux_width;
+            info!("Trying MUX-width: {}", width);
+            let (solution, refined) = try_partition(&mut context);
+            if solution.is_some() {
+                auto.escalations.push(Escalation::MuxWidth {
+                    from: mux_width,
+                    to: width,
+                });
+            }
+            if auto.finish(solution, refined) {
+                return auto;
+            }
+        }
+
+        // More correlator units
+        for units in num_units + 1..=2 * num_units {
+            let mut context = auto.initial.clone();
+            context.set_num_units(units);
+            info!("Trying correlator units: {}", units);
+            let (solution, refined) = try_partition(&mut context);
+            if solution.is_some() {
+                auto.escalations.push(Escalation::Units {
+                    from: num_units,
+                    to: units,
+                });
+            }
+            if auto.finish(solution, refined) {
+                return auto;
+            }
+        }
+
+        warn!("No solution found, after escalation");
+        auto
+    }
+
+    /**
+     *  Record the solution, if found, along with its refinement.
+     */
+    fn finish(
+        &mut self,
+        solution: Option<Solution>,
+        refined: Option<Escalation>,
+    ) -> bool {
+        if solution.is_none() {
+            return false;
+        }
+        if let Some(refined) = refined {
+            self.escalations.push(refined);
+        }
+        self.solution = solution;
+        true
+    }
+
+    /**
+     *  Differences in the estimated FPGA resources, from the derived to the
+     *  escalated configuration.
+     */
+    pub fn cost_delta(
+        &self,
+        widths: &BitWidths,
+        table: &CostTable,
+    ) -> Option<String> {
+        let solution = self.solution.as_ref()?;
+        let x = Estimate::new(&self.initial, widths, table);
+        let y = Estimate::new(&solution.context, widths, table);
+        let delta = |name: &str, x: usize, y: usize| {
+            format!("{}: {} -> {} ({:+})", name, x, y, y as i64 - x as i64)
+        };
+        Some(
+            [
+                delta("luts", x.luts, y.luts),
+                delta("flip_flops", x.flip_flops, y.flip_flops),
+                delta("brams", x.brams, y.brams),
+                format!("fmax: {:.1} -> {:.1} MHz", x.fmax, y.fmax),
+            ]
+            .join(", "),
+        )
+    }
+}
+
+impl fmt::Display for AutoSolve {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (x, y) = match &self.solution {
+            Some(solution) => (&self.initial, &solution.context),
+            None => return writeln!(f, "Automatic solve: FAILED !!"),
+        };
+        if self.escalations.is_empty() {
+            writeln!(f, "Automatic solve: no escalation was needed")?;
+        } else {
+            let steps: Vec<String> =
+                self.escalations.iter().map(|e| e.to_string()).collect();
+            writeln!(f, "Automatic solve, escalated: {}", steps.join(", "))?;
+        }
+        for e in self.escalations.iter() {
+            if let Escalation::Refinement(moves) = e {
+                for r in moves.iter() {
+                    writeln!(f, "    {}", r)?;
+                }
+            }
+        }
+        let (xi, yi) = (
+            x.num_units * (x.a_width() + x.b_width()),
+            y.num_units * (y.a_width() + y.b_width()),
+        );
+        writeln!(
+            f,
+            "Cost: units {} -> {}, MUX-width {} -> {}, MUX inputs {} -> {}",
+            x.num_units, y.num_units, x.mux_width, y.mux_width, xi, yi
+        )
+    }
+}
@@ -0,0 +1,282 @@
+use clap::Parser;
+use log::{error, info};
+use std::io::Write;
+use tart_dsp::{
+    beats_from_bytes, check_frame, logger, parse_beats, AntennaArray,
+    BaselineMap, Calibration, Context, Decoder, Endian, Pointing, Quantiser,
+    Solution, UvwVisibility, VisibilityMatrix, L1_FREQUENCY,
+};
+
+/// Decodes the visibilities from the 8-bit AXI4-Stream output of the TART
+/// correlator, as read back over the UART/USB, into visibility matrices. The
+/// correlator configuration is either loaded from a (YAML) solution file, as
+/// saved by 'tart-dsp --save', or regenerated using the same settings as for
+/// 'tart-dsp'.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Captured output stream, as raw bytes, or as a text dump (see '--text')
+    #[arg(value_name = "FILE")]
+    input: String,
+
+    /// Input is a text dump, with the hex byte and 'tlast' on each line
+    #[arg(short, long)]
+    text: bool,
+
+    /// Solution file (YAML), for the correlator configuration
+    #[arg(short, long, value_name = "FILE")]
+    solution: Option<String>,
+
+    /// Number of antennas/sources
+    #[arg(short, long, value_name = "NUM", default_value = "8")]
+    antennas: usize,
+
+    /// Clock ratio/multiplier for the correlators, relative to the input source.
+    #[arg(short, long, value_name = "FACTOR", default_value = "12")]
+    multiplier: usize,
+
+    /// Do not compute the signal-means, when this is enabled.
+    #[arg(short, long, value_name = "BOOL", default_value = "false")]
+    no_means: bool,
+
+    /// Number of extra MUX-width inputs, for more difficult configurations
+    #[arg(short, long, value_name = "BITS", default_value = "0")]
+    extra_bits: usize,
+
+    /// Compute only the visibilities of this (YAML or JSON) list of antenna-
+    /// pairs, instead of all of them
+    #[arg(long, value_name = "FILE")]
+    edges: Option<String>,
+
+    /// Antennas have X- & Y- feeds, and compute the XX, YY, XY, and YX products
+    #[arg(long)]
+    dual_pol: bool,
+
+    /// Number of correlator units [default: derived from the calculations]
+    #[arg(long, value_name = "NUM")]
+    units: Option<usize>,
+
+    /// Number of A-MUX inputs [default: derived MUX-width]
+    #[arg(long, value_name = "NUM")]
+    a_width: Option<usize>,
+
+    /// Number of B-MUX inputs [default: derived MUX-width]
+    #[arg(long, value_name = "NUM")]
+    b_width: Option<usize>,
+
+    /// Dead antennas, whose visibilities and signal-means are not computed
+    #[arg(long, value_name = "LIST", value_delimiter = ',')]
+    dead: Vec<usize>,
+
+    /// Correlator units (e.g., faulty tiles) that are given no assignments
+    #[arg(long, value_name = "LIST", value_delimiter = ',')]
+    disable_units: Vec<usize>,
+
+    /// Bit-width of each visibility component (i.e., 'ACCUM')
+    #[arg(short, long, value_name = "BITS", default_value = "36")]
+    width: usize,
+
+    /// Visibility words are sent most-significant byte first
+    #[arg(short, long)]
+    big_endian: bool,
+
+    /// Number of samples per integration, for calibrating the visibilities
+    /// into (Van Vleck corrected) correlation coefficients
+    #[arg(long, value_name = "NUM")]
+    samples: Option<usize>,
+
+    /// Expected (raw) visibilities and signal-means (CSV) file, as written by
+    /// 'test_vectors --raw', to check each frame against
+    #[arg(long, value_name = "FILE")]
+    expected: Option<String>,
+
+    /// Antenna positions file (YAML or JSON), of (east, north, up) offsets,
+    /// for computing the (u, v, w) of each visibility
+    #[arg(short, long, value_name = "FILE")]
+    positions: Option<String>,
+
+    /// Observing frequency, in Hz
+    #[arg(short, long, value_name = "HZ", default_value_t = L1_FREQUENCY)]
+    frequency: f64,
+
+    /// Azimuth of the phase-centre, in degrees (east of north)
+    #[arg(long, value_name = "DEG", default_value = "0")]
+    azimuth: f64,
+
+    /// Elevation of the phase-centre, in degrees
+    #[arg(long, value_name = "DEG", default_value = "90")]
+    elevation: f64,
+
+    /// Latitude of the array, in degrees, overriding the positions file
+    #[arg(long, value_name = "DEG")]
+    latitude: Option<f64>,
+
+    /// Write the baseline metadata, for each output, to this (YAML) file
+    #[arg(long, value_name = "FILE")]
+    baselines: Option<String>,
+
+    /// Write the decoded visibilities, and their (u, v, w) in wavelengths, to
+    /// this (CSV) file
+    #[arg(short, long, value_name = "FILE")]
+    uvw: Option<String>,
+
+    /// Verbosity
+    #[arg(short, long, value_name = "LEVEL")]
+    log_level: Option<String>,
+}
+
+fn load_solution(args: &Args) -> Result<Solution, Box<dyn std::error::Error>> {
+    if let Some(path) = &args.solution {
+        let file = std::fs::File::open(path)?;
+        return Ok(serde_yaml::from_reader(file)?);
+    }
+
+    let edges = match &args.edges {
+        Some(path) => Some(Context::load_edges(path)?),
+        None => None,
+    };
+    let context = match &edges {
+        _ if args.dual_pol => Context::dual_pol(
+            args.antennas,
+            edges.as_deref(),
+            args.multiplier,
+            args.no_means,
+            args.extra_bits,
+        )?,
+        Some(edges) => Context::with_edges(
+            args.antennas,
+            edges,
+            args.multiplier,
+            args.no_means,
+            args.extra_bits,
+        )?,
+        None => Context::new(
+            args.antennas,
+            args.multiplier,
+            args.no_means,
+            args.extra_bits,
+        ),
+    };
+    let mut context = context
+        .with_masks(&args.dead, &args.disable_units)?
+        .with_overrides(args.units, args.a_width, args.b_width)?;
+    context.partition(false);
+    Ok(Solution::solve(&mut context).ok_or("No correlator solution")?)
+}
+
+fn load_baselines(
+    args: &Args,
+    solution: &Solution,
+) -> Result<Option<BaselineMap>, Box<dyn std::error::Error>> {
+    let path = match &args.positions {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+    let array = AntennaArray::load(path)?;
+    let latitude = args.latitude.unwrap_or(array.latitude);
+    let pointing =
+        Pointing::from_horizontal(args.azimuth, args.elevation, latitude);
+    let map = BaselineMap::new(solution, &array, args.frequency, pointing)?;
+    info!("{}", map);
+
+    if let Some(path) = &args.baselines {
+        std::fs::write(path, serde_yaml::to_string(&map)?)?;
+        info!("Baselines written to: {}", path);
+    }
+    Ok(Some(map))
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Args = Args::parse();
+    let level: String = args.log_level.clone().unwrap_or("info".to_string());
+    logger::configure(level.as_str(), false)?;
+
+    let solution = load_solution(&args)?;
+    let endian = if args.big_endian {
+        Endian::Big
+    } else {
+        Endian::Little
+    };
+    let decoder = Decoder::new(args.width, endian);
+    let baselines = load_baselines(&args, &solution)?;
+    let calibration = args
+        .samples
+        .map(|n| Calibration::new(Quantiser::one_bit(), n));
+    let mut uvw_file = match (&args.uvw, &baselines) {
+        (Some(path), Some(_)) => {
+            let mut file = std::fs::File::create(path)?;
+            let header = if solution.context.dual_pol {
+                UvwVisibility::CSV_HEADER_POL
+            } else {
+                UvwVisibility::CSV_HEADER
+            };
+            writeln!(file, "frame,{}", header)?;
+            Some(file)
+        }
+        (Some(_), None) => {
+            return Err("Writing '--uvw' requires '--positions'".into())
+        }
+        _ => None,
+    };
+    let reference = match &args.expected {
+        Some(path) => Some(VisibilityMatrix::from_csv(
+            &std::fs::read_to_string(path)?,
+            solution.context.num_antennas,
+        )?),
+        None => None,
+    };
+    let mut failures = 0;
+    let expected = solution.num_outputs();
+
+    let beats = if args.text {
+        parse_beats(&std::fs::read_to_string(&args.input)?)?
+    } else {
+        let bytes = std::fs::read(&args.input)?;
+        beats_from_bytes(&bytes, decoder.frame_bytes(expected))
+    };
+
+    let frames = Decoder::split_frames(&beats);
+    info!(
+        "Decoding {} frames, of {} visibilities",
+        frames.len(),
+        expected
+    );
+
+    for (i, frame) in frames.iter().enumerate() {
+        match decoder.decode_frame(frame, expected) {
+            Ok(words) => {
+                let matrix = VisibilityMatrix::from_frame(&solution, &words);
+                println!("Frame {}:\n{}", i, matrix);
+                if let Some(reference) = &reference {
+                    let errors = check_frame(&solution, &words, reference);
+                    for e in errors.iter() {
+                        error!("Frame {}: {}", i, e);
+                    }
+                    if errors.is_empty() {
+                        info!("Frame {}: matches the expected values", i);
+                    } else {
+                        failures += 1;
+                    }
+                }
+                if let Some(cal) = &calibration {
+                    println!("Calibrated:\n{}", cal.frame(&solution, &words)?);
+                }
+                if let (Some(file), Some(map)) = (&mut uvw_file, &baselines) {
+                    for vis in map.visibilities(&words) {
+                        writeln!(file, "{},{}", i, vis.to_csv())?;
+                    }
+                }
+            }
+            Err(e) => {
+                error!("Frame {}: {}", i, e);
+                failures += 1;
+            }
+        }
+    }
+
+    if reference.is_some() && failures > 0 {
+        error!("{} of {} frames failed", failures, frames.len());
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use clap::Parser;
+use log::info;
+use tart_dsp::{
+    logger, point_source, read_visibilities, AntennaArray, Calibration,
+    DirtyImage, Pointing, Quantiser, L1_FREQUENCY, SPEED_OF_LIGHT,
+};
+
+/// Synthesises a dirty, all-sky image from correlator visibilities, as
+/// written by 'test_vectors --vis', or by 'tart-decode --uvw', and the
+/// antenna positions. The image is written as a PGM or FITS file.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Antenna positions file (YAML or JSON), of (east, north, up) offsets
+    #[arg(short, long, value_name = "FILE")]
+    positions: String,
+
+    /// Visibilities (CSV) file, with 'a', 'b', 're', and 'im' columns
+    #[arg(short, long, value_name = "FILE")]
+    vis: Option<String>,
+
+    /// Frame to image, for visibilities with a 'frame' column
+    #[arg(long, value_name = "NUM", default_value = "0")]
+    frame: usize,
+
+    /// Image the model visibilities of a point source, at the direction-
+    /// cosines 'l,m', instead of reading visibilities
+    #[arg(long, value_name = "L,M", allow_hyphen_values = true)]
+    source: Option<String>,
+
+    /// Normalise by the auto-correlations, and apply the Van Vleck correction
+    #[arg(short, long)]
+    calibrate: bool,
+
+    /// Number of ADC bits, for the Van Vleck correction
+    #[arg(short, long, value_name = "BITS", default_value = "1")]
+    bits: usize,
+
+    /// Value of the 2-bit samples with the magnitude-bit set
+    #[arg(long, value_name = "LEVEL", default_value = "3")]
+    magnitude: f64,
+
+    /// Image width and height, in pixels
+    #[arg(short, long, value_name = "PIXELS", default_value = "128")]
+    size: usize,
+
+    /// Grid the visibilities and use an FFT (for a power-of-two size),
+    /// instead of the direct Fourier transform
+    #[arg(long)]
+    fft: bool,
+
+    /// Observing frequency, in Hz
+    #[arg(short, long, value_name = "HZ", default_value_t = L1_FREQUENCY)]
+    frequency: f64,
+
+    /// Azimuth of the phase-centre, in degrees (east of north)
+    #[arg(long, value_name = "DEG", default_value = "0")]
+    azimuth: f64,
+
+    /// Elevation of the phase-centre, in degrees
+    #[arg(long, value_name = "DEG", default_value = "90")]
+    elevation: f64,
+
+    /// Latitude of the array, in degrees, overriding the positions file
+    #[arg(long, value_name = "DEG")]
+    latitude: Option<f64>,
+
+    /// Output image file, as FITS for a '.fits' extension, else as PGM
+    #[arg(short, long, value_name = "FILE", default_value = "dirty.pgm")]
+    output: String,
+
+    /// Verbosity
+    #[arg(short, long, value_name = "LEVEL")]
+    log_level: Option<String>,
+}
+
+fn parse_lm(text: &str) -> Result<(f64, f64), String> {
+    let lm: Vec<f64> = text
+        .split(',')
+        .map(|x| x.trim().parse::<f64>().map_err(|e| e.to_string()))
+        .collect::<Result<_, _>>()?;
+    match lm[..] {
+        [l, m] => Ok((l, m)),
+        _ => Err(format!("Expected 'l,m', not: {}", text)),
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Args = Args::parse();
+    let level: String = args.log_level.clone().unwrap_or("info".to_string());
+    logger::configure(level.as_str(), false)?;
+
+    let array = AntennaArray::load(&args.positions)?;
+    let latitude = args.latitude.unwrap_or(array.latitude);
+    let pointing =
+        Pointing::from_horizontal(args.azimuth, args.elevation, latitude);
+    let wavelength = SPEED_OF_LIGHT / args.frequency;
+
+    let vis = if let Some(source) = &args.source {
+        let (l, m) = parse_lm(source)?;
+        let n = array.num_antennas();
+        let uvws: Vec<[f64; 3]> = (0..n)
+            .flat_map(|a| (a + 1..n).map(move |b| (a, b)))
+            .map(|(a, b)| {
+                pointing
+                    .project(array.baseline(a, b))
+                    .map(|x| x / wavelength)
+            })
+            .collect();
+        point_source(&uvws, l, m)
+    } else if let Some(path) = &args.vis {
+        let source = std::fs::read_to_string(path)?;
+        let quantiser = Quantiser::new(args.bits, args.magnitude)?;
+        let calibration = Calibration::new(quantiser, 0);
+        read_visibilities(
+            &source,
+            &array,
+            wavelength,
+            &pointing,
+            args.frame,
+            args.calibrate.then_some(&calibration),
+        )?
+    } else {
+        return Err("Either '--vis' or '--source' is required".into());
+    };
+    info!("Imaging {} visibilities", vis.len());
+
+    let image = if args.fft {
+        DirtyImage::gridded(&vis, args.size)?
+    } else {
+        DirtyImage::dft(&vis, args.size)
+    };
+    let (l, m, peak) = image.peak();
+    println!("Peak of {:.4} at (l, m) = ({:.4}, {:.4})", peak, l, m);
+
+    if args.output.ends_with(".fits") {
+        std::fs::write(&args.output, image.to_fits())?;
+    } else {
+        std::fs::write(&args.output, image.to_pgm())?;
+    }
+    println!("Image written to: {}", args.output);
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use clap::Parser;
+use log::{error, info, warn};
+use tart_dsp::{logger, sigsource_params, LegacyPairs, LEGACY_BLOCKS};
+
+/// Imports the (legacy) TART2 correlator-pairs tables, the 'PAIRSxx_yy'
+/// parameters of 'tart_24x_radio.v', checks that every visibility is computed,
+/// and then converts them into the 'sigsource' tap & select parameters ('xTAPS'
+/// and 'xSELS'), along with the required MUX-width.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Verilog source containing the 'PAIRSxx_yy' parameters
+    #[arg(value_name = "FILE")]
+    input: String,
+
+    /// Number of time-slices for each correlator
+    #[arg(short, long, value_name = "FACTOR", default_value = "12")]
+    multiplier: usize,
+
+    /// Swap (conjugate) pairs, to minimise the MUX-widths
+    #[arg(short, long)]
+    reorient: bool,
+
+    /// Write the 'sigsource' parameters (Verilog) to this file
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<String>,
+
+    /// Write the (re-exported) 'PAIRSxx_yy' parameters to this file
+    #[arg(short, long, value_name = "FILE")]
+    pairs: Option<String>,
+
+    /// Save the equivalent correlator solution (YAML) to this file
+    #[arg(long, value_name = "FILE")]
+    save: Option<String>,
+
+    /// Verbosity
+    #[arg(short, long, value_name = "LEVEL")]
+    log_level: Option<String>,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Args = Args::parse();
+    let level: String = args.log_level.clone().unwrap_or("info".to_string());
+    logger::configure(level.as_str(), false)?;
+
+    let source = std::fs::read_to_string(&args.input)?;
+    let mut pairs = LegacyPairs::parse(&source, args.multiplier)?;
+    info!(
+        "Imported {} correlators, for {} antennas (MUX-width: {})",
+        pairs.num_units(),
+        pairs.num_antennas,
+        pairs.mux_width()
+    );
+    if args.reorient {
+        pairs = pairs.reoriented();
+        info!("Reoriented MUX-width: {}", pairs.mux_width());
+    }
+    println!("{}", pairs);
+
+    for (u, t) in pairs.repeats() {
+        info!("Correlator {}, time-slice {}: repeated calculation", u, t);
+    }
+
+    let solution = pairs.to_solution();
+    let missing = solution.missing_edges();
+    if missing.is_empty() {
+        println!(
+            "All {} visibilities are computed",
+            solution.context.num_edges
+        );
+    } else {
+        for &e in missing.iter() {
+            warn!("Missing visibility: {:?}", solution.context.edges_array[e]);
+        }
+        error!("{} visibilities are not computed", missing.len());
+    }
+
+    let params = sigsource_params(&solution);
+    match &args.output {
+        Some(path) => {
+            std::fs::write(path, &params)?;
+            println!("Parameters written to: {}", path);
+        }
+        None => println!("{}", params),
+    }
+
+    if let Some(path) = &args.pairs {
+        let legacy = LegacyPairs::from_solution(&solution, LEGACY_BLOCKS)?;
+        std::fs::write(path, legacy.to_verilog())?;
+        println!("PAIRS parameters written to: {}", path);
+    }
+
+    if let Some(path) = &args.save {
+        let file = std::fs::File::create(path)?;
+        serde_yaml::to_writer(file, &solution)?;
+        println!("Solution saved to: {}", path);
+    }
+    Ok(())
+}
@@ -0,0 +1,286 @@
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::fmt;
+
+use crate::solution::Solution;
+
+/**
+ * Threshold (in units of the RMS signal-level) of the 2-bit magnitude bit, for
+ * which the AGC of the MAX2769 keeps the magnitude bit high for 33% of the
+ * samples.
+ */
+pub const MAGNITUDE_THRESHOLD: f64 = 0.9674;
+
+/**
+ * Quantiser of the radio ADCs, where 1-bit samples are +/-1, and 2-bit samples
+ * are +/-1, or +/-'magnitude' when beyond the 'threshold'.
+ *
+ * The correlation of quantised Gaussian signals is a non-linear function of
+ * the correlation coefficient of the analog signals, which is the Van Vleck
+ * relation, 'r = (2/pi) asin(rho)', for 1-bit data. For 2-bit data, the curve
+ * is computed from Price's theorem, as the integral of:
+ *
+ *   dE/d(rho) = sum_jk h_j h_k p(t_j, t_k; rho)
+ *
+ * where 'h_j' are the steps of the quantiser at the thresholds 't_j', and 'p'
+ * is the bivariate normal PDF. Using 'rho = sin(theta)' removes the
+ * singularity at 'rho = 1'.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Quantiser {
+    pub bits: usize,
+    pub threshold: f64,
+    pub magnitude: f64,
+}
+
+impl Quantiser {
+    pub fn one_bit() -> Self {
+        Self {
+            bits: 1,
+            threshold: 0.0,
+            magnitude: 1.0,
+        }
+    }
+
+    /**
+     *  2-bit quantiser, for the given weight of the magnitude-bit values (3,
+     *  for the correlator truth-tables).
+     */
+    pub fn two_bit(magnitude: f64) -> Self {
+        Self {
+            bits: 2,
+            threshold: MAGNITUDE_THRESHOLD,
+            magnitude,
+        }
+    }
+
+    pub fn new(bits: usize, magnitude: f64) -> Result<Self, String> {
+        match bits {
+            1 => Ok(Self::one_bit()),
+            2 => Ok(Self::two_bit(magnitude)),
+            _ => {
+                Err(format!("Only 1- and 2- bit data are supported: {}", bits))
+            }
+        }
+    }
+
+    /**
+     *  Expected product of the quantised signals, for the analog correlation
+     *  coefficient 'rho', and without normalisation.
+     */
+    fn product(&self, rho: f64) -> f64 {
+        if self.bits == 1 {
+            return 2.0 / PI * rho.asin();
+        }
+        let (t2, step) = (self.threshold.powi(2), self.magnitude - 1.0);
+        let slope = |theta: f64| {
+            let (s, c) = theta.sin_cos();
+            let edges = (-t2 / (2.0 * c * c)).exp();
+            let same = (-t2 / (1.0 + s)).exp();
+            let opposite = if s < 1.0 {
+                (-t2 / (1.0 - s)).exp()
+            } else {
+                0.0
+            };
+            4.0 + 8.0 * step * edges + 2.0 * step * step * (same + opposite)
+        };
+
+        // Simpson's rule, over 'theta' in '[0, asin(rho)]'
+        let steps = 64;
+        let h = rho.clamp(-1.0, 1.0).asin() / steps as f64;
+        let sum: f64 = (0..=steps)
+            .map(|i| {
+                let w = match i {
+                    0 => 1.0,
+                    _ if i == steps => 1.0,
+                    _ if i % 2 == 1 => 4.0,
+                    _ => 2.0,
+                };
+                w * slope(i as f64 * h)
+            })
+            .sum();
+        sum * h / 3.0 / (2.0 * PI)
+    }
+
+    /**
+     *  Normalised correlation of the quantised signals, for the analog
+     *  correlation coefficient 'rho'.
+     */
+    pub fn correlation(&self, rho: f64) -> f64 {
+        self.product(rho) / self.product(1.0)
+    }
+
+    /**
+     *  Van Vleck correction, which estimates the analog correlation
+     *  coefficient from the normalised correlation, 'r', of the quantised
+     *  signals, by inverting 'correlation(rho)'.
+     */
+    pub fn correct(&self, r: f64) -> f64 {
+        let r = r.clamp(-1.0, 1.0);
+        if self.bits == 1 {
+            return (PI / 2.0 * r).sin();
+        }
+        let (mut lo, mut hi) = (0.0, 1.0);
+        let target = r.abs() * self.product(1.0);
+        for _ in 0..48 {
+            let mid = 0.5 * (lo + hi);
+            if self.product(mid) < target {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        0.5 * (lo + hi) * r.signum()
+    }
+}
+
+/**
+ * Estimates of the analog correlation coefficients, for each antenna-pair, and
+ * the mean (per component) of each of the quantised signals, where known.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalibratedMatrix {
+    pub num_antennas: usize,
+    pub values: Vec<Option<(f64, f64)>>,
+    pub means: Vec<Option<f64>>,
+}
+
+impl CalibratedMatrix {
+    pub fn get(&self, a: usize, b: usize) -> Option<(f64, f64)> {
+        self.values[a * self.num_antennas + b]
+    }
+}
+
+impl fmt::Display for CalibratedMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let n = self.num_antennas;
+        for a in 0..n {
+            let row: Vec<String> = (0..n)
+                .map(|b| match (a == b, self.get(a, b)) {
+                    (true, _) => match self.means[a] {
+                        Some(m) => format!("{:>16}", format!("<{:+.4}>", m)),
+                        None => format!("{:>16}", "-"),
+                    },
+                    (false, Some((re, im))) => {
+                        format!("{:>16}", format!("{:+.4}{:+.4}j", re, im))
+                    }
+                    (false, None) => format!("{:>16}", "?"),
+                })
+                .collect();
+            writeln!(f, "{}", row.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+/**
+ * Calibration of the raw correlator outputs, into correlation coefficients.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Calibration {
+    pub quantiser: Quantiser,
+    pub samples: usize,
+}
+
+impl Calibration {
+    pub fn new(quantiser: Quantiser, samples: usize) -> Self {
+        Self { quantiser, samples }
+    }
+
+    /**
+     *  Correlation coefficient of a visibility, from the quantised correlation
+     *  components, '(re, im)', normalised by the RMS levels of both signals,
+     *  and with the product of the means, 'mu_a * mu_b', removed from the real
+     *  component (as the means cancel for the imaginary component). Then the
+     *  Van Vleck correction is applied to each component.
+     */
+    pub fn coefficient(
+        &self,
+        (re, im): (f64, f64),
+        means: (f64, f64),
+        scale: f64,
+    ) -> (f64, f64) {
+        let (ma, mb) = means;
+        let re = (re - ma * mb) / scale;
+        let im = im / scale;
+        (self.quantiser.correct(re), self.quantiser.correct(im))
+    }
+
+    /**
+     *  Calibrate a decoded frame, of the 1-bit correlator, where each raw
+     *  visibility component is the sum of the '{0, 1, 2}' terms (one per
+     *  sample), and each signal-mean is the number of high I & Q bits. Both
+     *  are offset by 'samples', so that each normalised component is:
+     *
+     *    r = value / samples - 1
+     *
+     *  Visibilities of antennas without signal-means are assumed to have zero
+     *  means.
+     */
+    pub fn frame(
+        &self,
+        solution: &Solution,
+        words: &[(i64, i64)],
+    ) -> Result<CalibratedMatrix, String> {
+        if self.quantiser.bits != 1 {
+            return Err("The correlator only supports 1-bit data".to_string());
+        }
+        if self.samples == 0 {
+            return Err("Number of samples must be non-zero".to_string());
+        }
+        let n = solution.context.num_antennas;
+        let samples = self.samples as f64;
+        let norm = |x: i64| x as f64 / samples - 1.0;
+        let order = solution.output_order();
+
+        let mut means = vec![None; n];
+        for (slot, &(re, im)) in order.iter().zip(words) {
+            match slot {
+                Some(s) if s.means => {
+                    means[s.a] = Some(norm(re));
+                    means[s.b] = Some(norm(im));
+                }
+                _ => {}
+            }
+        }
+
+        let mut values = vec![None; n * n];
+        for (slot, &(re, im)) in order.iter().zip(words) {
+            let s = match slot {
+                Some(s) if !s.means => s,
+                _ => continue,
+            };
+            let (ma, mb) =
+                (means[s.a].unwrap_or(0.0), means[s.b].unwrap_or(0.0));
+            let scale = ((1.0 - ma * ma) * (1.0 - mb * mb)).sqrt();
+            let (re, im) = self.coefficient(
+                (norm(re), norm(im)),
+                (ma, mb),
+                scale.max(f64::EPSILON),
+            );
+            values[s.a * n + s.b] = Some((re, im));
+            values[s.b * n + s.a] = Some((re, -im));
+        }
+
+        Ok(CalibratedMatrix {
+            num_antennas: n,
+            values,
+            means,
+        })
+    }
+
+    /**
+     *  Calibrate the (two's-complement) visibility sum of a pair of signals,
+     *  normalised by the auto-correlations of both signals, for example, as
+     *  computed by the reference correlator of the test-vectors.
+     */
+    pub fn visibility(
+        &self,
+        (re, im): (f64, f64),
+        auto_a: f64,
+        auto_b: f64,
+    ) -> (f64, f64) {
+        let scale = (auto_a * auto_b).sqrt().max(f64::EPSILON);
+        self.coefficient((re, im), (0.0, 0.0), scale)
+    }
+}
@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 use std::fmt;
 
 use crate::chunked::Chunked;
+use crate::flow::FlowNetwork;
 
 /**
  * Stores the working data for partitioning the set of visibility calculations
@@ -37,7 +38,16 @@ pub struct Context {
     /* (Current, A- & B-) MUX assignments */
     pub a_mux_array: Chunked<usize>,
     pub b_mux_array: Chunked<usize>,
-    pub means_array: Chunked<(usize, usize)>,
+
+    /* Excluded antennas, and correlator units, for degraded configurations */
+    #[serde(default)]
+    pub dead_antennas: Vec<usize>,
+    #[serde(default)]
+    pub disabled_units: Vec<usize>,
+
+    /* Nodes are the X- & Y- feeds of the antennas (see 'dual_pol') */
+    #[serde(default)]
+    pub dual_pol: bool,
 }
 
 impl fmt::Display for Context {
@@ -50,6 +60,9 @@ impl fmt::Display for Context {
         writeln!(f, "    num_edges: {}", self.num_edges)?;
         writeln!(f, "    num_units: {}", self.num_units)?;
         writeln!(f, "    mux_width: {}", self.mux_width)?;
+        if self.dual_pol {
+            writeln!(f, "    dual_pol: {}", self.dual_pol)?;
+        }
 
         fn from_count(prefix: &'static str, c: usize) -> String {
             if c > 0 {
@@ -99,13 +112,98 @@ impl Context {
         extra_bits: usize,
     ) -> Self {
         let edges_array = Context::make_edges(num_antennas);
-        let num_edges: usize = edges_array.len();
-        let num_calcs: usize = if no_means {
+        Self::from_edges(
+            num_antennas,
+            edges_array,
+            clock_multiplier,
+            no_means,
+            extra_bits,
+        )
+    }
+
+    /**
+     *  Context for computing just the given visibilities (edges), instead of
+     *  every antenna-pair, where each edge is stored with its lower antenna-
+     *  index first, and the edges are sorted.
+     */
+    pub fn with_edges(
+        num_antennas: usize,
+        edges: &[(usize, usize)],
+        clock_multiplier: usize,
+        no_means: bool,
+        extra_bits: usize,
+    ) -> Result<Self, String> {
+        let mut edges_array = Vec::with_capacity(edges.len());
+        for &(a, b) in edges.iter() {
+            if a == b {
+                return Err(format!("Invalid edge: {} -> {}", a, b));
+            }
+            if a.max(b) >= num_antennas {
+                return Err(format!(
+                    "Edge {} -> {} is not between the {} antennas",
+                    a, b, num_antennas
+                ));
+            }
+            edges_array.push((a.min(b), a.max(b)));
+        }
+        edges_array.sort_unstable();
+        edges_array.dedup();
+        if edges_array.is_empty() {
+            return Err("No edges to compute".to_string());
+        }
+
+        Ok(Self::from_edges(
+            num_antennas,
+            edges_array,
+            clock_multiplier,
+            no_means,
+            extra_bits,
+        ))
+    }
+
+    /**
+     *  Parses a list of edges, as '[a, b]' antenna-pairs, from YAML or JSON
+     *  (as JSON is also valid YAML).
+     */
+    pub fn parse_edges(source: &str) -> Result<Vec<(usize, usize)>, String> {
+        let edges: Vec<[usize; 2]> =
+            serde_yaml::from_str(source).map_err(|e| e.to_string())?;
+        Ok(edges.into_iter().map(|[a, b]| (a, b)).collect())
+    }
+
+    pub fn load_edges(path: &str) -> Result<Vec<(usize, usize)>, String> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read '{}': {}", path, e))?;
+        Self::parse_edges(&source)
+    }
+
+    /**
+     *  Number of calculations, for the edges, and for the signal-means of the
+     *  nodes (unless 'no_means'), where each signal-means slot computes the
+     *  means of two nodes.
+     */
+    fn num_calcs(num_edges: usize, num_nodes: usize, no_means: bool) -> usize {
+        if no_means {
             num_edges
         } else {
-            (num_antennas * num_antennas) >> 1
-        };
-        let num_units: usize =
+            num_edges + num_nodes.div_ceil(2)
+        }
+    }
+
+    fn from_edges(
+        num_antennas: usize,
+        edges_array: Vec<(usize, usize)>,
+        clock_multiplier: usize,
+        no_means: bool,
+        extra_bits: usize,
+    ) -> Self {
+        let num_edges: usize = edges_array.len();
+        let num_pairs: usize = (num_antennas * (num_antennas - 1)) >> 1;
+        let num_nodes: usize = (0..num_antennas)
+            .filter(|&x| edges_array.iter().any(|&(a, b)| a == x || b == x))
+            .count();
+        let num_calcs: usize = Self::num_calcs(num_edges, num_nodes, no_means);
+        let mut num_units: usize =
             (num_calcs as f64 / clock_multiplier as f64).ceil() as usize;
 
         // If using means, then '+1', and if fewer correlators than antennas,
@@ -116,9 +214,13 @@ impl Context {
         }
         let width0: usize =
             ((clock_multiplier + extras) as f64).sqrt().ceil() as usize;
-        let width1: usize = num_antennas >> 1;
+        let width1: usize = num_nodes >> 1;
         let mux_width: usize = width1.min(width0 + extra_bits);
 
+        // Sparse subsets may need more units, so that every node has a MUX
+        // input
+        num_units = num_units.max(num_nodes.div_ceil(2 * mux_width));
+
         Self {
             num_antennas,
             clock_multiplier,
@@ -130,12 +232,16 @@ impl Context {
 
             edges_array,
             edges_count: vec![0; num_edges],
-            pairs_count: vec![0; num_edges],
+            pairs_count: vec![0; num_pairs],
             nodes_count: vec![0; num_antennas],
 
             a_mux_array: Chunked::new(mux_width, num_units),
             b_mux_array: Chunked::new(mux_width, num_units),
-            means_array: Chunked::new(mux_width, num_units),
+
+            dead_antennas: Vec::new(),
+            disabled_units: Vec::new(),
+
+            dual_pol: false,
         }
     }
 
@@ -143,13 +249,13 @@ impl Context {
 
     pub fn can_insert_a_node(&self, unit: usize, node: usize) -> bool {
         (self.a_mux_array[unit].contains(&node)
-            || self.a_mux_array[unit].len() < self.mux_width)
+            || self.a_mux_array[unit].len() < self.a_width())
             && !self.b_mux_array[unit].contains(&node)
     }
 
     pub fn can_insert_b_node(&self, unit: usize, node: usize) -> bool {
         (self.b_mux_array[unit].contains(&node)
-            || self.b_mux_array[unit].len() < self.mux_width)
+            || self.b_mux_array[unit].len() < self.b_width())
             && !self.a_mux_array[unit].contains(&node)
     }
 
@@ -166,7 +272,7 @@ impl Context {
         // Update all node pairs-counts due to the other MUX inputs
         for dest in self.a_mux_array[unit].iter() {
             // For each A-MUX node, increase the corresponding A-A pairs count
-            let index = self.calc_edge_index(node, *dest);
+            let index = self.calc_pair_index(node, *dest);
             self.pairs_count[index] += 1;
         }
 
@@ -176,7 +282,9 @@ impl Context {
         // Compute any new edges due to the new A-MUX node
         for dest in self.b_mux_array[unit].iter() {
             // For each B-MUX node, increase the corresponding A-B edge count
-            let index = self.calc_edge_index(node, *dest);
+            let Some(index) = self.find_edge(node, *dest) else {
+                continue;
+            };
             let e_num = self.edges_count[index];
 
             // The edge is new, so increment the new-edge counter
@@ -203,7 +311,7 @@ impl Context {
         // Update all node pairs-counts due to the other MUX inputs
         for dest in self.b_mux_array[unit].iter() {
             // For each B-MUX node, increase the corresponding B-B pairs count
-            let index = self.calc_edge_index(node, *dest);
+            let index = self.calc_pair_index(node, *dest);
             self.pairs_count[index] += 1;
         }
 
@@ -213,7 +321,9 @@ impl Context {
         // Compute any new edges due to the new B-MUX node
         for dest in self.a_mux_array[unit].iter() {
             // For each A-MUX node, increase the corresponding A-B edge count
-            let index = self.calc_edge_index(node, *dest);
+            let Some(index) = self.find_edge(node, *dest) else {
+                continue;
+            };
             let e_num = self.edges_count[index];
 
             // The edge is new, so increment the new-edge counter
@@ -235,8 +345,11 @@ impl Context {
         node_a: usize,
         node_b: usize,
     ) -> usize {
+        // The product of a node with itself is its auto-correlation, whereas
+        // the (cross-polarisation) product of the two feeds of an antenna is
+        // between two nodes (see 'dual_pol')
         if node_a == node_b {
-            panic!("RETARTED!!");
+            panic!("Auto-correlation of node {} is not an edge", node_a);
         }
         // Insert the A-MUX node, if not present, and update edge-counts
         self.insert_a_node(unit, node_a) + self.insert_b_node(unit, node_b)
@@ -248,7 +361,7 @@ impl Context {
      *  Computed by expanding out the expressions for the upper-triangular
      *  matrix coordinates to array-index.
      */
-    pub fn calc_edge_index(&self, node_a: usize, node_b: usize) -> usize {
+    fn calc_pair_index(&self, node_a: usize, node_b: usize) -> usize {
         let src = if node_a > node_b { node_b } else { node_a };
         let dst = if node_a > node_b { node_a } else { node_b };
         assert!(src < dst);
@@ -256,6 +369,29 @@ impl Context {
         ((src * ((self.num_antennas << 1) - 3 - src)) >> 1) + dst - 1
     }
 
+    /**
+     *  Index of the edge between the two nodes, if it is to be computed. All
+     *  antenna-pairs are edges, unless the context was built from a subset of
+     *  the edges, which are then (sorted and) searched.
+     */
+    pub fn find_edge(&self, node_a: usize, node_b: usize) -> Option<usize> {
+        if !self.is_subset() {
+            return Some(self.calc_pair_index(node_a, node_b));
+        }
+        let edge = (node_a.min(node_b), node_a.max(node_b));
+        self.edges_array.binary_search(&edge).ok()
+    }
+
+    /**
+     *  Index of the edge between the two nodes, which must be an edge.
+     */
+    pub fn calc_edge_index(&self, node_a: usize, node_b: usize) -> usize {
+        match self.find_edge(node_a, node_b) {
+            Some(index) => index,
+            None => panic!("Not an edge: {} -> {}", node_a, node_b),
+        }
+    }
+
     fn make_edges(num_antennas: usize) -> Vec<(usize, usize)> {
         let num_edges: usize = (num_antennas * (num_antennas - 1)) >> 1;
         let mut edges: Vec<(usize, usize)> =
@@ -281,8 +417,68 @@ impl Context {
         self.edges_count[index]
     }
 
+    /**
+     *  Number of units that compute the edge, or 'None' if it is not an edge.
+     */
+    pub fn edge_count(&self, node_a: usize, node_b: usize) -> Option<usize> {
+        self.find_edge(node_a, node_b).map(|e| self.edges_count[e])
+    }
+
     pub fn is_complete(&self) -> bool {
-        self.edges_count.iter().all(|x| *x > 0)
+        (0..self.num_edges)
+            .all(|e| self.edges_count[e] > 0 || !self.is_required(e))
+    }
+
+    pub fn is_dead(&self, node: usize) -> bool {
+        self.dead_antennas.contains(&node)
+    }
+
+    /**
+     *  Whether just a subset of the antenna-pairs are edges.
+     */
+    pub fn is_subset(&self) -> bool {
+        self.num_edges < self.pairs_count.len()
+    }
+
+    /**
+     *  Antennas are active unless they are dead, or are not in any edge (of a
+     *  subset), and only active antennas are given MUX inputs, and have their
+     *  signal-means computed.
+     */
+    pub fn is_active(&self, node: usize) -> bool {
+        !self.is_dead(node)
+            && (!self.is_subset()
+                || self
+                    .edges_array
+                    .iter()
+                    .any(|&(a, b)| a == node || b == node))
+    }
+
+    pub fn num_active(&self) -> usize {
+        (0..self.num_antennas)
+            .filter(|&x| self.is_active(x))
+            .count()
+    }
+
+    pub fn is_disabled(&self, unit: usize) -> bool {
+        self.disabled_units.contains(&unit)
+    }
+
+    /**
+     *  Edges are required unless either of their antennas is dead.
+     */
+    pub fn is_required(&self, edge: usize) -> bool {
+        let (a, b) = self.edges_array[edge];
+        !self.is_dead(a) && !self.is_dead(b)
+    }
+
+    /**
+     *  Edges that are no longer computed, due to dead antennas.
+     */
+    pub fn dropped_edges(&self) -> Vec<usize> {
+        (0..self.num_edges)
+            .filter(|&e| !self.is_required(e))
+            .collect()
     }
 
     /**
@@ -318,33 +514,61 @@ impl Context {
      *    placing any MUX inputs, for the means calculations.
      */
     fn place_means(&mut self) -> usize {
-        let mut unit = 0;
-        let mut node = 0;
         let mut edges = 0;
 
         debug!("Clearing any current edge & mean assignments.");
         // std::process::exit(1);
         self.reset();
 
-        while node < self.num_antennas {
-            // Handle the case where we need multiple passes, over each of the
-            // correlators, to place all of the sources/antennas/nodes.
-            if unit >= self.num_units {
-                unit = 0;
-            }
+        // Skipping any inactive antennas, and disabled units, making multiple
+        // passes over the correlators, if needed, to place all of the nodes.
+        let nodes: Vec<usize> = (0..self.num_antennas)
+            .filter(|&n| self.is_active(n))
+            .collect();
+        let units: Vec<usize> = (0..self.num_units)
+            .filter(|&u| !self.is_disabled(u))
+            .collect();
+        if units.is_empty() {
+            return edges;
+        }
 
-            let temp = node + 1;
-            if temp >= self.num_antennas {
-                // Only insert just the 'A' node, and update the edge-set
-                edges += self.insert_a_node(unit, node);
-                // self.means_array.push(unit, (node, usize::MAX));
-            } else {
-                edges += self.insert_node_pair(unit, node, temp);
-                // self.means_array.push(unit, (node, temp));
+        for (i, pair) in nodes.chunks(2).enumerate() {
+            // Starting from the next unit, in round-robin order
+            let order: Vec<usize> = (0..units.len())
+                .map(|j| units[(i + j) % units.len()])
+                .collect();
+            if let [node, temp] = *pair {
+                let unit = order.iter().copied().find(|&u| {
+                    self.can_insert_a_node(u, node)
+                        && self.can_insert_b_node(u, temp)
+                });
+                if let Some(unit) = unit {
+                    edges += self.insert_node_pair(unit, node, temp);
+                    continue;
+                }
             }
 
-            unit += 1;
-            node += 2;
+            // Otherwise, place each node into any MUX with a free input
+            for &node in pair.iter() {
+                let a_unit = order
+                    .iter()
+                    .copied()
+                    .find(|&u| self.can_insert_a_node(u, node));
+                let b_unit = order
+                    .iter()
+                    .copied()
+                    .find(|&u| self.can_insert_b_node(u, node));
+                match (a_unit, b_unit) {
+                    (Some(u), _) => edges += self.insert_a_node(u, node),
+                    (None, Some(u)) => edges += self.insert_b_node(u, node),
+                    (None, None) => {
+                        error!(
+                            "Cannot place antenna {}, as all MUXs are full",
+                            node
+                        )
+                    }
+                }
+            }
         }
 
         edges
@@ -360,6 +584,18 @@ impl Context {
         self.mux_width
     }
 
+    /**
+     *  Number of inputs of each A-MUX, and of each B-MUX, which may differ,
+     *  and where 'mux_width' is the wider of the two.
+     */
+    pub fn a_width(&self) -> usize {
+        self.a_mux_array.get_stride()
+    }
+
+    pub fn b_width(&self) -> usize {
+        self.b_mux_array.get_stride()
+    }
+
     pub fn get_num_antennas(&self) -> usize {
         self.num_antennas
     }
@@ -377,20 +613,17 @@ impl Context {
      *  and nodes, lowers the priority for it to be considered for placement.
      */
     fn node_score(&self, node: usize) -> (usize, usize, usize) {
-        let mut edge_score = 0;
-        let mut pair_score = 0;
-        let mut index = 0;
-
-        for i in 0..self.num_antennas {
-            for j in i + 1..self.num_antennas {
-                if i == node || j == node {
-                    edge_score += self.edges_count[index];
-                    pair_score += self.pairs_count[index];
-                }
-
-                index += 1;
-            }
-        }
+        let edge_score: usize = (0..self.num_edges)
+            .filter(|&e| {
+                let (i, j) = self.edges_array[e];
+                i == node || j == node
+            })
+            .map(|e| self.edges_count[e])
+            .sum();
+        let pair_score: usize = (0..self.num_antennas)
+            .filter(|&i| i != node)
+            .map(|i| self.pairs_count[self.calc_pair_index(node, i)])
+            .sum();
 
         (edge_score, pair_score, self.nodes_count[node])
     }
@@ -402,7 +635,8 @@ impl Context {
      */
     fn a_mux_score(&self, unit: usize, node: usize) -> (usize, usize, usize) {
         // Can not insert into both A- & B- MUXs, or already in A-MUX
-        if self.b_mux_array[unit].contains(&node)
+        if !self.is_active(node)
+            || self.b_mux_array[unit].contains(&node)
             || self.a_mux_array[unit].contains(&node)
         {
             return (usize::MAX, usize::MAX, usize::MAX);
@@ -413,18 +647,25 @@ impl Context {
         let mut edge_score = 0;
         let mut dups_score = edges + pairs;
 
-        // Update the edge-score due to insertion of 'node'
+        // Update the edge-score due to insertion of 'node', where products that
+        // are not edges (of a subset) are wasted, so count as duplicates
         for r in self.b_mux_array[unit].iter() {
-            let e = self.calc_edge_index(node, *r);
-            if self.edges_count[e] > 0 {
-                edge_score += 1;
-                dups_score += self.edges_count[e] + 1;
+            match self.find_edge(node, *r) {
+                Some(e) if self.edges_count[e] > 0 => {
+                    edge_score += 1;
+                    dups_score += self.edges_count[e] + 1;
+                }
+                Some(_) => {}
+                None => {
+                    edge_score += 1;
+                    dups_score += 1;
+                }
             }
         }
 
         // Update the pair-score due to insertion of 'node'
         for r in self.a_mux_array[unit].iter() {
-            let e = self.calc_edge_index(node, *r);
+            let e = self.calc_pair_index(node, *r);
             dups_score += self.pairs_count[e] + 1;
         }
 
@@ -433,7 +674,8 @@ impl Context {
 
     fn b_mux_score(&self, unit: usize, node: usize) -> (usize, usize, usize) {
         // Can not insert into both A- & B- MUXs, or already in B-MUX
-        if self.a_mux_array[unit].contains(&node)
+        if !self.is_active(node)
+            || self.a_mux_array[unit].contains(&node)
             || self.b_mux_array[unit].contains(&node)
         {
             return (usize::MAX, usize::MAX, usize::MAX);
@@ -444,18 +686,25 @@ impl Context {
         let mut edge_score = 0;
         let mut dups_score = edges + pairs;
 
-        // Update the edge-score due to insertion of 'node'
+        // Update the edge-score due to insertion of 'node', where products that
+        // are not edges (of a subset) are wasted, so count as duplicates
         for r in self.a_mux_array[unit].iter() {
-            let e = self.calc_edge_index(node, *r);
-            if self.edges_count[e] > 0 {
-                edge_score += 1;
-                dups_score += self.edges_count[e] + 1;
+            match self.find_edge(node, *r) {
+                Some(e) if self.edges_count[e] > 0 => {
+                    edge_score += 1;
+                    dups_score += self.edges_count[e] + 1;
+                }
+                Some(_) => {}
+                None => {
+                    edge_score += 1;
+                    dups_score += 1;
+                }
             }
         }
 
         // Update the pair-score due to insertion of 'node'
         for r in self.b_mux_array[unit].iter() {
-            let e = self.calc_edge_index(node, *r);
+            let e = self.calc_pair_index(node, *r);
             dups_score += self.pairs_count[e] + 1;
         }
 
@@ -494,10 +743,13 @@ impl Context {
     }
 
     pub fn fill_unit(&mut self, unit: usize) {
-        while self.num_nodes_at(unit) < 2 * self.mux_width {
-            // Add node to the emptiest MUX
+        let (a_width, b_width) = (self.a_width(), self.b_width());
+        while self.num_nodes_at(unit) < a_width + b_width {
+            // Add node to the emptiest MUX, that is not yet full
+            let (a_len, b_len) =
+                (self.a_mux_array[unit].len(), self.b_mux_array[unit].len());
             let mux_b: bool =
-                self.a_mux_array[unit].len() > self.b_mux_array[unit].len();
+                a_len >= a_width || (b_len < b_width && a_len > b_len);
 
             if mux_b {
                 self.place_b_mux(unit);
@@ -512,19 +764,20 @@ impl Context {
         for a in a_mux.iter() {
             for o in a_mux.iter() {
                 if *o > *a {
-                    score += self.pairs_count[self.calc_edge_index(*a, *o)] - 1;
+                    score += self.pairs_count[self.calc_pair_index(*a, *o)] - 1;
                 }
             }
             for b in b_mux.iter() {
-                let e = self.calc_edge_index(*a, *b);
-                score += self.edges_count[e] - 1;
+                if let Some(e) = self.find_edge(*a, *b) {
+                    score += self.edges_count[e] - 1;
+                }
             }
             score += self.nodes_count[*a] - 1;
         }
         for b in b_mux.iter() {
             for o in b_mux.iter() {
                 if *o > *b {
-                    score += self.pairs_count[self.calc_edge_index(*b, *o)] - 1;
+                    score += self.pairs_count[self.calc_pair_index(*b, *o)] - 1;
                 }
             }
             score += self.nodes_count[*b] - 1;
@@ -559,11 +812,159 @@ impl Context {
     }
 
     /**
-     *  Sorts the nodes-order for each MUX, if each set of MUX inputs is full.
+     *  Change the number of correlator units, which also clears all of the
+     *  current assignments.
+     */
+    pub fn set_num_units(&mut self, num_units: usize) {
+        let (a_width, b_width) = (self.a_width(), self.b_width());
+        self.num_units = num_units;
+        self.a_mux_array = Chunked::new(a_width, num_units);
+        self.b_mux_array = Chunked::new(b_width, num_units);
+        self.reset();
+    }
+
+    /**
+     *  Change the (possibly different) widths of the A- & B- MUXs, which also
+     *  clears all of the current assignments.
+     */
+    pub fn set_mux_widths(
+        &mut self,
+        a_width: usize,
+        b_width: usize,
+    ) -> Result<(), String> {
+        if a_width == 0 || b_width == 0 {
+            return Err("MUX widths must be non-zero".to_string());
+        }
+        if a_width + b_width > self.num_active() {
+            return Err(format!(
+                "MUX widths ({} + {}) exceed the number of antennas ({})",
+                a_width,
+                b_width,
+                self.num_active()
+            ));
+        }
+        self.mux_width = a_width.max(b_width);
+        self.a_mux_array = Chunked::new(a_width, self.num_units);
+        self.b_mux_array = Chunked::new(b_width, self.num_units);
+        self.reset();
+        Ok(())
+    }
+
+    /**
+     *  Exclude the dead antennas (and both of their feeds, for dual-
+     *  polarisation), so that their visibilities and signal-means are not
+     *  computed, and their MUX inputs are given to other antennas, and exclude
+     *  the disabled units, which are then given no assignments. If the MUXs
+     *  are too wide for the live antennas, then the MUX-width, and the number
+     *  of units, are re-derived for just the live antennas. This also clears
+     *  all of the current assignments.
+     */
+    pub fn with_masks(
+        mut self,
+        dead: &[usize],
+        disabled: &[usize],
+    ) -> Result<Self, String> {
+        if let Some(&n) = dead.iter().find(|&&n| n >= self.num_dishes()) {
+            return Err(format!("Invalid dead antenna: {}", n));
+        }
+        if let Some(&u) = disabled.iter().find(|&&u| u >= self.num_units) {
+            return Err(format!("Invalid disabled unit: {}", u));
+        }
+        self.dead_antennas =
+            dead.iter().flat_map(|&n| self.antenna_nodes(n)).collect();
+        self.dead_antennas.sort_unstable();
+        self.dead_antennas.dedup();
+        self.disabled_units = disabled.to_vec();
+        self.disabled_units.sort_unstable();
+        self.disabled_units.dedup();
+
+        let alive = self.num_active();
+        if alive < 2 {
+            return Err(format!("Too few live antennas ({})", alive));
+        }
+        if alive < self.a_width() + self.b_width() {
+            // Clamp the MUX-width (as for an edge-subset), and then add units
+            // if needed, for the calculations of the live antennas, and so
+            // that every live antenna has a MUX input
+            let width = self.mux_width.min(alive >> 1);
+            let edges = self
+                .edges_array
+                .iter()
+                .filter(|&&(a, b)| self.is_active(a) && self.is_active(b))
+                .count();
+            let calcs = Self::num_calcs(edges, alive, self.no_means);
+            let units = calcs
+                .div_ceil(self.clock_multiplier)
+                .max(alive.div_ceil(2 * width))
+                + self.disabled_units.len();
+            warn!(
+                "Too few live antennas ({}) for the MUX widths ({} + {})",
+                alive,
+                self.a_width(),
+                self.b_width()
+            );
+            self.set_num_units(self.num_units.max(units));
+            self.set_mux_widths(width, width)?;
+        }
+        if self.disabled_units.len() >= self.num_units {
+            return Err("All correlator units are disabled".to_string());
+        }
+        self.reset();
+        Ok(self)
+    }
+
+    /**
+     *  Apply any given overrides, of the number of units, and of the A- & B-
+     *  MUX widths, where a missing width keeps the derived width. These are
+     *  applied after any masks, so that the overrides are kept.
+     */
+    pub fn with_overrides(
+        mut self,
+        num_units: Option<usize>,
+        a_width: Option<usize>,
+        b_width: Option<usize>,
+    ) -> Result<Self, String> {
+        if let Some(units) = num_units {
+            if units == 0 {
+                return Err("Number of units must be non-zero".to_string());
+            }
+            let calcs = Self::num_calcs(
+                self.num_edges,
+                self.num_active(),
+                self.no_means,
+            );
+            if units * self.clock_multiplier < calcs {
+                warn!(
+                    "Too few time-slots ({} units x {}) for {} calculations",
+                    units, self.clock_multiplier, calcs
+                );
+            }
+            if let Some(&u) = self.disabled_units.iter().find(|&&u| u >= units)
+            {
+                return Err(format!("Invalid disabled unit: {}", u));
+            }
+            if self.disabled_units.len() >= units {
+                return Err("All correlator units are disabled".to_string());
+            }
+            self.set_num_units(units);
+        }
+        if a_width.is_some() || b_width.is_some() {
+            let a_width = a_width.unwrap_or(self.a_width());
+            let b_width = b_width.unwrap_or(self.b_width());
+            self.set_mux_widths(a_width, b_width)?;
+        }
+        Ok(self)
+    }
+
+    /**
+     *  Sorts the nodes-order for each MUX, where the MUXs of disabled units
+     *  are empty.
      */
     pub fn sort_inputs(&mut self) {
-        self.a_mux_array.sort_chunks();
-        self.b_mux_array.sort_chunks();
+        for u in 0..self.num_units {
+            self.a_mux_array[u].sort_unstable();
+            self.b_mux_array[u].sort_unstable();
+        }
     }
 
     pub fn replace(&mut self, unit: usize, curr: usize, next: usize) {
@@ -574,17 +975,19 @@ impl Context {
                 if *r == curr {
                     index = i;
                     for j in self.b_mux_array[unit].iter() {
-                        let e = self.calc_edge_index(curr, *j);
-                        self.edges_count[e] -= 1;
-                        let e = self.calc_edge_index(next, *j);
-                        self.edges_count[e] += 1;
+                        if let Some(e) = self.find_edge(curr, *j) {
+                            self.edges_count[e] -= 1;
+                        }
+                        if let Some(e) = self.find_edge(next, *j) {
+                            self.edges_count[e] += 1;
+                        }
                     }
                     self.nodes_count[curr] -= 1;
                     self.nodes_count[next] += 1;
                 } else {
-                    let e = self.calc_edge_index(curr, *r);
+                    let e = self.calc_pair_index(curr, *r);
                     self.pairs_count[e] -= 1;
-                    let e = self.calc_edge_index(next, *r);
+                    let e = self.calc_pair_index(next, *r);
                     self.pairs_count[e] += 1;
                 }
             }
@@ -594,17 +997,19 @@ impl Context {
                 if *r == curr {
                     index = i;
                     for j in self.a_mux_array[unit].iter() {
-                        let e = self.calc_edge_index(curr, *j);
-                        self.edges_count[e] -= 1;
-                        let e = self.calc_edge_index(next, *j);
-                        self.edges_count[e] += 1;
+                        if let Some(e) = self.find_edge(curr, *j) {
+                            self.edges_count[e] -= 1;
+                        }
+                        if let Some(e) = self.find_edge(next, *j) {
+                            self.edges_count[e] += 1;
+                        }
                     }
                     self.nodes_count[curr] -= 1;
                     self.nodes_count[next] += 1;
                 } else {
-                    let e = self.calc_edge_index(curr, *r);
+                    let e = self.calc_pair_index(curr, *r);
                     self.pairs_count[e] -= 1;
-                    let e = self.calc_edge_index(next, *r);
+                    let e = self.calc_pair_index(next, *r);
                     self.pairs_count[e] += 1;
                 }
             }
@@ -625,8 +1030,9 @@ impl Context {
             Vec::from(&self.b_mux_array[unit])
                 .iter()
                 .map(|b| {
-                    let e = self.calc_edge_index(node, *b);
-                    self.edges_count[e] + self.pairs_count[e]
+                    let e = self.find_edge(node, *b);
+                    e.map_or(0, |e| self.edges_count[e])
+                        + self.pairs_count[self.calc_pair_index(node, *b)]
                 })
                 .sum::<usize>()
                 + self.nodes_count[node]
@@ -634,8 +1040,9 @@ impl Context {
             Vec::from(&self.a_mux_array[unit])
                 .iter()
                 .map(|a| {
-                    let e = self.calc_edge_index(node, *a);
-                    self.edges_count[e] + self.pairs_count[e]
+                    let e = self.find_edge(node, *a);
+                    e.map_or(0, |e| self.edges_count[e])
+                        + self.pairs_count[self.calc_pair_index(node, *a)]
                 })
                 .sum::<usize>()
                 + self.nodes_count[node]
@@ -649,14 +1056,16 @@ impl Context {
     }
 
     pub fn find_unneeded(&self) -> Chunked<usize> {
-        let mut unneeded =
-            Chunked::<usize>::new(self.mux_width << 1, self.num_units);
+        let mut unneeded = Chunked::<usize>::new(
+            self.a_width() + self.b_width(),
+            self.num_units,
+        );
 
         for u in 0..self.num_units {
             for a in self.a_mux_array[u].iter() {
                 if Vec::from(&self.b_mux_array[u])
                     .iter()
-                    .all(|b| self.edges_count[self.calc_edge_index(*a, *b)] > 1)
+                    .all(|b| self.edge_count(*a, *b) != Some(1))
                 {
                     unneeded.push(u, *a);
                 }
@@ -665,7 +1074,7 @@ impl Context {
             for b in self.b_mux_array[u].iter() {
                 if Vec::from(&self.a_mux_array[u])
                     .iter()
-                    .all(|a| self.edges_count[self.calc_edge_index(*a, *b)] > 1)
+                    .all(|a| self.edge_count(*a, *b) != Some(1))
                 {
                     unneeded.push(u, *b);
                 }
@@ -706,7 +1115,9 @@ impl Context {
         for unit in 0..self.num_units {
             for i in self.a_mux_array[unit].iter() {
                 for j in self.b_mux_array[unit].iter() {
-                    let k = self.calc_edge_index(*i, *j);
+                    let Some(k) = self.find_edge(*i, *j) else {
+                        continue;
+                    };
                     let p = colptrs[k + 1];
                     indices[p] = unit;
                     colptrs[k + 1] = p + 1;
@@ -718,68 +1129,236 @@ impl Context {
     }
 
     /**
-     *  Assigns edges to each correlator, in order of least-frequent to most-
-     *  frequent edges.
+     *  Units that can compute the signal-means job, which is either a pair of
+     *  antennas, with one in each MUX of the unit, or a single antenna, in
+     *  either MUX of the unit.
+     */
+    fn means_units(&self, (x, y): (usize, Option<usize>)) -> Vec<usize> {
+        (0..self.num_units)
+            .filter(|&u| {
+                let (a_mux, b_mux) =
+                    (&self.a_mux_array[u], &self.b_mux_array[u]);
+                match y {
+                    Some(y) => {
+                        a_mux.contains(&x) && b_mux.contains(&y)
+                            || b_mux.contains(&x) && a_mux.contains(&y)
+                    }
+                    None => a_mux.contains(&x) || b_mux.contains(&x),
+                }
+            })
+            .collect()
+    }
+
+    /**
+     *  Pairs up the antennas, for computing their signal-means, where each
+     *  pair must share at least one unit (with one antenna in each MUX). The
+     *  antennas with the fewest partners are paired first, and any antennas
+     *  left over are single jobs.
+     */
+    pub fn means_jobs(&self) -> Vec<(usize, Option<usize>)> {
+        let n = self.num_antennas;
+        let partners: Vec<Vec<usize>> = (0..n)
+            .map(|x| {
+                (0..n)
+                    .filter(|&y| {
+                        y != x
+                            && self.is_active(y)
+                            && !self.means_units((x, Some(y))).is_empty()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut order: Vec<usize> =
+            (0..n).filter(|&x| self.is_active(x)).collect();
+        order.sort_by_key(|&x| partners[x].len());
+        let mut paired = vec![false; n];
+        let mut jobs = Vec::with_capacity(n);
+        for x in order {
+            if paired[x] {
+                continue;
+            }
+            paired[x] = true;
+            let y = partners[x]
+                .iter()
+                .copied()
+                .filter(|&y| !paired[y])
+                .min_by_key(|&y| partners[y].len());
+            if let Some(y) = y {
+                paired[y] = true;
+            }
+            jobs.push((x, y));
+        }
+        jobs
+    }
+
+    /**
+     *  Assigns each edge, and each signal-means job, to one of the correlator
+     *  units that can compute it, where each unit has 'clock_multiplier'
+     *  time-slots. This is a bipartite b-matching, of jobs to units, so is
+     *  solved as the maximum flow of the network:
+     *
+     *    source --(1)--> job --(1)--> unit --(time-slots)--> sink
      *
-     *  Note: When more than one correlator unit can be chosen, choose the
-     *    least-populated correlator. This heuristic is vulnerable to certain
-     *    edge-cases, but so far seems to perform well enough.
+     *  which finds a complete assignment whenever one exists. Otherwise, the
+     *  jobs without flow are the (smallest possible) set of jobs that cannot
+     *  be routed. Returns the edges of each unit, the unit of each (routed)
+     *  signal-means job, the unrouted edges, and the number of time-slots used
+     *  by each unit.
+     *
+     *  Note: the 'i'-th slot of each unit has cost 'i', and the flow of least
+     *    cost is used, so that the jobs are spread evenly across the units.
+     *    Edges are also given a (negative) cost, that is larger than that of
+     *    any slot, so that the edges are routed in preference to the means.
      */
-    pub fn assign_edges(&mut self, cont: bool) -> Option<Chunked<usize>> {
-        let ranks = self.sorted_edges();
-        if self.edges_count[ranks[0]] == 0 {
-            error!("Not all edges have been covered!");
-            let mut i = 0;
-            let mut missing: Vec<String> = Vec::new();
-            while self.edges_count[ranks[i]] == 0 {
-                let (a, b) = self.edges_array[ranks[i]];
-                missing.push(format!("{} -> {}", a, b));
-                i += 1;
+    pub fn route_jobs(
+        &self,
+        means: &[(usize, Option<usize>)],
+    ) -> (Chunked<usize>, Vec<Option<usize>>, Vec<usize>, Vec<usize>) {
+        let (ptrs, idxs) = self.find_edge_units();
+        let (num_edges, num_units) = (self.num_edges, self.num_units);
+        let num_jobs = num_edges + means.len();
+        let (source, sink) = (0, num_jobs + num_units + 1);
+        let unit = |u: usize| num_jobs + u + 1;
+        let mut network = FlowNetwork::new(sink + 1);
+        let bonus = -(self.clock_multiplier as i64);
+
+        let mut arcs = Vec::with_capacity(idxs.len());
+        for k in (0..num_edges).filter(|&k| self.is_required(k)) {
+            network.add_costed_arc(source, k + 1, 1, bonus);
+            for &u in idxs[ptrs[k]..ptrs[k + 1]].iter() {
+                arcs.push((k, u, network.add_arc(k + 1, unit(u), 1)));
+            }
+        }
+        for (i, &job) in means.iter().enumerate() {
+            let k = num_edges + i;
+            network.add_arc(source, k + 1, 1);
+            for u in self.means_units(job) {
+                arcs.push((k, u, network.add_arc(k + 1, unit(u), 1)));
+            }
+        }
+        for u in 0..num_units {
+            for slot in 0..self.clock_multiplier {
+                network.add_costed_arc(unit(u), sink, 1, slot as i64);
             }
-            let edges = missing.join(", ");
-            error!("Missing: {}  (num = {})", edges, i);
-            return None;
         }
+        let (routed, _) = network.min_cost_flow(source, sink);
+        debug!("Routed {} of {} jobs", routed, num_jobs);
 
         let mut units: Chunked<usize> =
-            Chunked::new(self.clock_multiplier, self.num_units);
-        let (ptrs, idxs) = self.find_edge_units();
+            Chunked::new(self.clock_multiplier, num_units);
+        let mut found = vec![false; num_edges];
+        let mut placed = vec![None; means.len()];
+        let mut slots = vec![0; num_units];
+        for &(k, u, arc) in arcs.iter() {
+            if network.flow(arc) > 0 {
+                if k < num_edges {
+                    units.push(u, k);
+                    found[k] = true;
+                } else {
+                    placed[k - num_edges] = Some(u);
+                }
+                slots[u] += 1;
+            }
+        }
+        let unrouted = (0..num_edges)
+            .filter(|&k| !found[k] && self.is_required(k))
+            .collect();
 
-        for k in ranks.into_iter() {
-            let c = self.edges_count[k];
-            let p = ptrs[k];
+        (units, placed, unrouted, slots)
+    }
 
-            let u = if c == 1 {
-                // Edge has to be assigned to the only unit containing it
-                idxs[p]
-            } else {
-                // Figure out the best unit to assign the edge to
-                let us: Vec<usize> = (idxs[p..p + c]).to_vec();
-                let mut qs: Vec<usize> = (0..us.len()).collect();
-                qs.sort_by_key(|&k| {
-                    units.count(us[k]) + self.means_array.count(us[k])
-                });
+    /**
+     *  Routes the edges, along with the signal-means (unless 'no_means' is
+     *  set), so that each unit computes the means of the jobs routed to it
+     *  (see 'means_pairs'). Whenever a pair of means cannot be routed, then
+     *  the pair is split into two single jobs, and the routing is repeated.
+     */
+    pub fn route_edges(&self) -> Routing {
+        let mut means = if self.no_means {
+            Vec::new()
+        } else {
+            self.means_jobs()
+        };
+        loop {
+            let (edges, placed, unrouted, slots) = self.route_jobs(&means);
+            let split: Vec<usize> = (0..means.len())
+                .filter(|&i| placed[i].is_none() && means[i].1.is_some())
+                .collect();
+            if split.is_empty() {
+                let unplaced = (0..means.len())
+                    .filter(|&i| placed[i].is_none())
+                    .map(|i| means[i].0)
+                    .collect();
+                return Routing {
+                    edges,
+                    means: self.means_pairs(&means, &placed),
+                    unrouted,
+                    unplaced,
+                    slots,
+                };
+            }
+            debug!("Splitting {} signal-means pairs", split.len());
+            for &i in split.iter() {
+                let (x, y) = means[i];
+                means[i] = (x, None);
+                means.push((y.unwrap(), None));
+            }
+        }
+    }
 
-                // todo: compute some form of "score" for the edge-insertion,
-                //   based on the edges already assigned, and those remaining.
-                // let (a, b) = self.edges_array[k];
-                us[qs[0]]
-            };
-            if units.count(u) + self.means_array.count(u)
-                < self.clock_multiplier
-                && units.can_push(u, k)
-            {
-                units.push(u, k);
-            } else if !cont {
-                error!("No solution, as there is no free correlator!\n");
+    /**
+     *  Assigns edges, and the signal-means, to each correlator unit (see
+     *  'route_edges'), where any edges that cannot be routed are reported, and
+     *  are then skipped if 'cont' is set. Any signal-means that cannot be
+     *  placed are also reported, and then there is no assignment.
+     */
+    pub fn assign_edges(&mut self, cont: bool) -> Option<Routing> {
+        let uncovered = self.uncovered_edges();
+        if !uncovered.is_empty() {
+            error!("Not all edges have been covered!");
+            let missing: Vec<String> = uncovered
+                .iter()
+                .map(|&e| {
+                    let (a, b) = self.edges_array[e];
+                    format!("{} -> {}", a, b)
+                })
+                .collect();
+            let edges = missing.join(", ");
+            error!("Missing: {}  (num = {})", edges, missing.len());
+            return None;
+        }
+
+        let routing = self.route_edges();
+        if !routing.unrouted.is_empty() {
+            let edges: Vec<String> = routing
+                .unrouted
+                .iter()
+                .map(|&k| {
+                    let (a, b) = self.edges_array[k];
+                    format!("{} -> {}", a, b)
+                })
+                .collect();
+            if !cont {
+                error!("No solution, as there is no free correlator!");
+                error!(
+                    "Unroutable: {}  (num = {})",
+                    edges.join(", "),
+                    edges.len()
+                );
                 return None;
-            } else {
-                let (a, b) = self.edges_array[k];
-                warn!("Failed to route edge: {} -> {}", a, b);
             }
+            for edge in edges.iter() {
+                warn!("Failed to route edge: {}", edge);
+            }
+        }
+
+        if !routing.unplaced.is_empty() {
+            self.explain_means(&routing.unplaced, &routing.slots);
+            return None;
         }
 
-        Some(units)
+        Some(routing)
     }
 
     /**
@@ -802,12 +1381,29 @@ impl Context {
             info!("Skipping means ...");
         }
 
-        // Filling both input MUXs of each correlator unit.
+        // Filling both input MUXs of each (enabled) correlator unit.
         for i in 0..self.num_units {
-            self.fill_unit(i);
+            if !self.is_disabled(i) {
+                self.fill_unit(i);
+            }
         }
 
         // Sort (ascending) the MUX inputs.
         self.sort_inputs();
     }
 }
+
+/**
+ * Assignment of the edges, and the signal-means pairs, to the correlator
+ * units, along with the edges that could not be routed, the antennas whose
+ * signal-means could not be placed, and the number of time-slots used by each
+ * unit.
+ */
+#[derive(Debug, Clone)]
+pub struct Routing {
+    pub edges: Chunked<usize>,
+    pub means: Chunked<(usize, usize)>,
+    pub unrouted: Vec<usize>,
+    pub unplaced: Vec<usize>,
+    pub slots: Vec<usize>,
+}
@@ -0,0 +1,418 @@
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::solution::Solution;
+
+/**
+ * Byte-order of each visibility word, within the 8-bit AXI4-Stream.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+/**
+ * A single transfer of the 8-bit AXI4-Stream, with its 'tlast' marker.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Beat {
+    pub data: u8,
+    pub last: bool,
+}
+
+/**
+ * Reassembles the 'ACCUM'-bit (two's-complement) real and imaginary components
+ * of each visibility, from the bytes of the correlator output stream. Each
+ * visibility is sent as a single '{re, im}' word, of '2*ACCUM' bits padded to
+ * whole bytes (by 'axis_adapter'), and least-significant byte first, so that
+ * the imaginary component is in the lower 'ACCUM' bits.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decoder {
+    pub width: usize,
+    pub endian: Endian,
+}
+
+impl Decoder {
+    pub fn new(width: usize, endian: Endian) -> Self {
+        Self { width, endian }
+    }
+
+    /**
+     *  Number of bytes used by each (complex) visibility word.
+     */
+    pub fn word_bytes(&self) -> usize {
+        (2 * self.width).div_ceil(8)
+    }
+
+    /**
+     *  Number of bytes for a frame of 'words' (complex) visibilities.
+     */
+    pub fn frame_bytes(&self, words: usize) -> usize {
+        words * self.word_bytes()
+    }
+
+    /**
+     *  Sign-extend the lower 'width' bits of the value.
+     */
+    fn extend(&self, value: u128) -> i64 {
+        let shift = 128 - self.width;
+        ((value << shift) as i128 >> shift) as i64
+    }
+
+    /**
+     *  Assemble a single visibility word, and then split it into its (sign-
+     *  extended) real and imaginary components.
+     */
+    pub fn word(&self, bytes: &[u8]) -> (i64, i64) {
+        let value = match self.endian {
+            Endian::Little => bytes
+                .iter()
+                .rev()
+                .fold(0u128, |acc, &b| (acc << 8) | b as u128),
+            Endian::Big => {
+                bytes.iter().fold(0u128, |acc, &b| (acc << 8) | b as u128)
+            }
+        };
+        (self.extend(value >> self.width), self.extend(value))
+    }
+
+    /**
+     *  Split the stream into frames, at each 'tlast' marker. Any trailing
+     *  bytes, without a 'tlast', are discarded.
+     */
+    pub fn split_frames(beats: &[Beat]) -> Vec<Vec<u8>> {
+        let mut frames = Vec::new();
+        let mut frame = Vec::new();
+
+        for beat in beats.iter() {
+            frame.push(beat.data);
+            if beat.last {
+                frames.push(std::mem::take(&mut frame));
+            }
+        }
+
+        if !frame.is_empty() {
+            warn!("Discarding {} bytes of an incomplete frame", frame.len());
+        }
+        frames
+    }
+
+    /**
+     *  Decode a frame into its (Re, Im) visibility components, after checking
+     *  that the frame has the expected number of visibilities.
+     */
+    pub fn decode_frame(
+        &self,
+        frame: &[u8],
+        expected: usize,
+    ) -> Result<Vec<(i64, i64)>, String> {
+        if self.width == 0 || self.width > 64 {
+            return Err(format!("Unsupported word-width: {}", self.width));
+        }
+        let length = self.frame_bytes(expected);
+        if frame.len() != length {
+            return Err(format!(
+                "Frame has {} bytes, but {} visibilities requires {} bytes",
+                frame.len(),
+                expected,
+                length
+            ));
+        }
+
+        Ok(frame
+            .chunks_exact(self.word_bytes())
+            .map(|v| self.word(v))
+            .collect())
+    }
+}
+
+/**
+ *  Parse a text dump of the stream, with one transfer per line, as the hex
+ *  byte and then an optional 'tlast' flag (e.g., "3f 1"). Blank lines, and
+ *  those starting with '#' or '//', are ignored.
+ */
+pub fn parse_beats(text: &str) -> Result<Vec<Beat>, String> {
+    let mut beats = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+            continue;
+        }
+
+        let mut fields = line.split_whitespace();
+        let data = fields.next().unwrap_or_default();
+        let data = u8::from_str_radix(data.trim_start_matches("0x"), 16)
+            .map_err(|e| format!("Line {}: invalid byte ({})", i + 1, e))?;
+        let last = match fields.next() {
+            None | Some("0") => false,
+            Some("1") => true,
+            Some(x) => {
+                return Err(format!("Line {}: invalid 'tlast': {}", i + 1, x))
+            }
+        };
+        beats.push(Beat { data, last });
+    }
+
+    Ok(beats)
+}
+
+/**
+ *  Raw byte-streams (e.g., UART read-back) carry no 'tlast' markers, so mark
+ *  the end of every 'frame_len' bytes.
+ */
+pub fn beats_from_bytes(bytes: &[u8], frame_len: usize) -> Vec<Beat> {
+    bytes
+        .iter()
+        .enumerate()
+        .map(|(i, &data)| Beat {
+            data,
+            last: frame_len > 0 && (i + 1) % frame_len == 0,
+        })
+        .collect()
+}
+
+/**
+ * Visibilities, indexed by antenna-pair, and the signal-means of each antenna,
+ * from a decoded frame. These are the raw (offset) values, so only the pair
+ * '(a, b)' of each output is stored, as 'V_ba' is not obtained by negating
+ * the raw 'im' (see 'Calibration', for the conjugates).
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VisibilityMatrix {
+    pub num_antennas: usize,
+    pub values: Vec<Option<(i64, i64)>>,
+    pub means: Vec<Option<i64>>,
+}
+
+impl fmt::Display for VisibilityMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let n = self.num_antennas;
+        for a in 0..n {
+            let row: Vec<String> = (0..n)
+                .map(|b| match (a == b, self.get(a, b)) {
+                    (true, _) => match self.means[a] {
+                        Some(m) => format!("{:>12}", format!("<{}>", m)),
+                        None => format!("{:>12}", "-"),
+                    },
+                    (false, Some((re, im))) => {
+                        format!("{:>12}", format!("{}{:+}j", re, im))
+                    }
+                    (false, None) if self.get(b, a).is_some() => {
+                        format!("{:>12}", "*")
+                    }
+                    (false, None) => format!("{:>12}", "?"),
+                })
+                .collect();
+            writeln!(f, "{}", row.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+impl VisibilityMatrix {
+    /**
+     *  Map each output of the frame to its antenna-pair, using the output-
+     *  order of the solution.
+     */
+    pub fn from_frame(solution: &Solution, words: &[(i64, i64)]) -> Self {
+        let n = solution.context.num_antennas;
+        let mut values = vec![None; n * n];
+        let mut means = vec![None; n];
+
+        for (slot, &(re, im)) in solution.output_order().iter().zip(words) {
+            match slot {
+                Some(s) if s.means => {
+                    means[s.a] = Some(re);
+                    means[s.b] = Some(im);
+                }
+                Some(s) => values[s.a * n + s.b] = Some((re, im)),
+                None => {}
+            }
+        }
+
+        Self {
+            num_antennas: n,
+            values,
+            means,
+        }
+    }
+
+    /**
+     *  Raw visibility for the antenna-pair, which is 'None' unless '(a, b)'
+     *  is the pair of an output (so that 'V_ba' is 'None', for 'V_ab').
+     */
+    pub fn get(&self, a: usize, b: usize) -> Option<(i64, i64)> {
+        self.values[a * self.num_antennas + b]
+    }
+
+    /**
+     *  Parse the expected visibilities, from CSV with the 'a,b,re,im' header
+     *  (e.g., from 'test_vectors --raw'), where the 'a == b' rows contain the
+     *  signal-mean of antenna 'a', as 're'. Rows are stored as given, as raw
+     *  (offset) visibilities are not conjugated by negating 'im'.
+     */
+    pub fn from_csv(source: &str, num_antennas: usize) -> Result<Self, String> {
+        let n = num_antennas;
+        let mut values = vec![None; n * n];
+        let mut means = vec![None; n];
+
+        let mut lines = source.lines().enumerate();
+        match lines.next() {
+            Some((_, header)) if header.trim() == "a,b,re,im" => {}
+            _ => return Err("Expected the header: 'a,b,re,im'".to_string()),
+        }
+        for (i, line) in lines.filter(|(_, l)| !l.trim().is_empty()) {
+            let fields: Vec<i64> = line
+                .split(',')
+                .map(|x| x.trim().parse::<i64>())
+                .collect::<Result<_, _>>()
+                .map_err(|e| format!("Line {}: {}", i + 1, e))?;
+            let (a, b, re, im) = match fields[..] {
+                [a, b, re, im] if a >= 0 && b >= 0 => {
+                    (a as usize, b as usize, re, im)
+                }
+                _ => return Err(format!("Line {}: invalid row", i + 1)),
+            };
+            if a.max(b) >= n {
+                return Err(format!("Line {}: antenna out of range", i + 1));
+            }
+            if a == b {
+                means[a] = Some(re);
+            } else {
+                values[a * n + b] = Some((re, im));
+            }
+        }
+
+        Ok(Self {
+            num_antennas: n,
+            values,
+            means,
+        })
+    }
+}
+
+/**
+ * Check each output of a decoded frame against the expected values, using the
+ * output-order of the solution, and describe each difference.
+ */
+pub fn check_frame(
+    solution: &Solution,
+    words: &[(i64, i64)],
+    expected: &VisibilityMatrix,
+) -> Vec<String> {
+    let mut errors = Vec::new();
+    for (i, (slot, &word)) in
+        solution.output_order().iter().zip(words).enumerate()
+    {
+        let (s, value) = match slot {
+            Some(s) if s.means => {
+                for (node, x) in [(s.a, word.0), (s.b, word.1)] {
+                    match expected.means.get(node).copied().flatten() {
+                        Some(y) if x != y => errors.push(format!(
+                            "output {}: mean {}: {} (expected: {})",
+                            i, node, x, y
+                        )),
+                        Some(_) => {}
+                        None => errors.push(format!(
+                            "output {}: no expected mean for {}",
+                            i, node
+                        )),
+                    }
+                }
+                continue;
+            }
+            Some(s) => (s, expected.get(s.a, s.b)),
+            None => continue,
+        };
+        match value {
+            Some(y) if word != y => errors.push(format!(
+                "output {}: ({}, {}): {}{:+}j (expected: {}{:+}j)",
+                i, s.a, s.b, word.0, word.1, y.0, y.1
+            )),
+            Some(_) => {}
+            None => errors.push(format!(
+                "output {}: no expected value for ({}, {})",
+                i, s.a, s.b
+            )),
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+     *  Width of the padded words, 'WBITS = 8 * ((2 * ACCUM + 7) / 8)', of the
+     *  generated top-level.
+     */
+    fn wbits(width: usize) -> usize {
+        let bits = 2 * width;
+        bits + (8 - bits % 8) % 8
+    }
+
+    /**
+     *  The '{re, im}' word, zero-extended to 'WBITS', and then as the bytes of
+     *  the stream.
+     */
+    fn stream_bytes(width: usize, re: i64, im: i64, endian: Endian) -> Vec<u8> {
+        let wbits = wbits(width);
+        let mask = (1u128 << width) - 1;
+        let value = ((re as u128 & mask) << width) | (im as u128 & mask);
+        let bytes = value.to_le_bytes()[..wbits / 8].to_vec();
+        match endian {
+            Endian::Little => bytes,
+            Endian::Big => bytes.into_iter().rev().collect(),
+        }
+    }
+
+    #[test]
+    fn word_bytes_match_the_padding() {
+        for width in 1..=64 {
+            let decoder = Decoder::new(width, Endian::Little);
+            assert_eq!(8 * decoder.word_bytes(), wbits(width));
+        }
+        assert_eq!(wbits(13), 32);
+        assert_eq!(wbits(12), 24);
+    }
+
+    #[test]
+    fn word_of_either_endianness() {
+        for endian in [Endian::Little, Endian::Big] {
+            // 'ACCUM' of 13 is padded to 32 bits, and 12 needs no padding
+            for width in [13, 12] {
+                let (min, max) =
+                    (-1i64 << (width - 1), (1i64 << (width - 1)) - 1);
+                let cases = [(5, -3), (min, max), (max, min), (-1, -1), (0, 1)];
+                let decoder = Decoder::new(width, endian);
+                for (re, im) in cases.into_iter() {
+                    let bytes = stream_bytes(width, re, im, endian);
+                    assert_eq!(bytes.len(), decoder.word_bytes());
+                    assert_eq!(decoder.word(&bytes), (re, im));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn split_frames_discards_a_trailing_frame() {
+        let beats = beats_from_bytes(&[1, 2, 3, 4, 5, 6, 7], 3);
+        let frames = Decoder::split_frames(&beats);
+        assert_eq!(frames, vec![vec![1, 2, 3], vec![4, 5, 6]]);
+    }
+
+    #[test]
+    fn decode_frame_checks_the_length() {
+        let decoder = Decoder::new(13, Endian::Little);
+        let mut frame = stream_bytes(13, -3, 5, Endian::Little);
+        frame.extend(stream_bytes(13, 7, -8, Endian::Little));
+        assert_eq!(decoder.decode_frame(&frame, 2), Ok(vec![(-3, 5), (7, -8)]));
+        assert!(decoder.decode_frame(&frame, 3).is_err());
+        assert!(decoder.decode_frame(&frame[..7], 2).is_err());
+    }
+}
@@ -0,0 +1,310 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::context::Context;
+use crate::refine::Replacement;
+
+/**
+ * A MUX input that could be replaced, to compute a missing visibility, and
+ * whether it is removable (so its visibilities are also computed elsewhere).
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MuxSlot {
+    pub unit: usize,
+    pub b_mux: bool,
+    pub index: usize,
+    pub node: usize,
+    pub next: usize,
+    pub removable: bool,
+}
+
+/**
+ * Why an edge is not computed, with the units whose MUXs contain each of its
+ * nodes, as '(unit, b_mux)', and the MUX inputs that would need to change.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EdgeReport {
+    pub edge: (usize, usize),
+    pub units_a: Vec<(usize, bool)>,
+    pub units_b: Vec<(usize, bool)>,
+    pub slots: Vec<MuxSlot>,
+    pub routable: Vec<usize>,
+}
+
+/**
+ * Diagnosis of a (partitioned) context, that fails to compute all of the
+ * visibilities, where:
+ *  - 'uncovered' edges are not contained by the MUXs of any unit;
+ *  - 'unrouted' edges are contained by units, but which have no free slots;
+ *  - 'replacements' is a minimal set of MUX-input replacements, that covers
+ *    all but the 'remaining' edges.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnosis {
+    pub clock_multiplier: usize,
+    pub mux_width: usize,
+    pub slots_used: Vec<usize>,
+    pub mux_full: Vec<(bool, bool)>,
+    pub uncovered: Vec<EdgeReport>,
+    pub unrouted: Vec<EdgeReport>,
+    pub replacements: Vec<Replacement>,
+    pub remaining: Vec<(usize, usize)>,
+}
+
+/**
+ * Units with the node in either MUX, as '(unit, b_mux)'.
+ */
+fn node_units(context: &Context, node: usize) -> Vec<(usize, bool)> {
+    (0..context.num_units)
+        .filter_map(|u| {
+            if context.a_mux_array[u].contains(&node) {
+                Some((u, false))
+            } else if context.b_mux_array[u].contains(&node) {
+                Some((u, true))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/**
+ * Applies the replacements, to a copy of the context, skipping any that no
+ * longer apply, and then returns the edges that remain uncovered.
+ */
+fn replay(context: &Context, moves: &[Replacement]) -> Vec<usize> {
+    let mut context = context.clone();
+    for r in moves.iter() {
+        let (mux, other) = if r.b_mux {
+            (&context.b_mux_array[r.unit], &context.a_mux_array[r.unit])
+        } else {
+            (&context.a_mux_array[r.unit], &context.b_mux_array[r.unit])
+        };
+        if mux.contains(&r.curr)
+            && !mux.contains(&r.next)
+            && !other.contains(&r.next)
+        {
+            context.replace(r.unit, r.curr, r.next);
+        }
+    }
+    context.uncovered_edges()
+}
+
+impl Diagnosis {
+    pub fn new(context: &Context) -> Self {
+        let routing = context.route_edges();
+        let mux_full = (0..context.num_units)
+            .map(|u| {
+                (
+                    context.a_mux_array[u].len() >= context.a_width(),
+                    context.b_mux_array[u].len() >= context.b_width(),
+                )
+            })
+            .collect();
+
+        let uncovered = context.uncovered_edges();
+        let unrouted = routing
+            .unrouted
+            .into_iter()
+            .filter(|e| !uncovered.contains(e))
+            .map(|e| Self::report(context, e))
+            .collect();
+
+        // Refine a copy of the context, and then drop each replacement that
+        // is not needed by the others
+        let mut refined = context.clone();
+        let mut moves = refined.refine();
+        let remaining = refined.uncovered_edges();
+        for i in (0..moves.len()).rev() {
+            let mut fewer = moves.clone();
+            fewer.remove(i);
+            if replay(context, &fewer).len() <= remaining.len() {
+                moves = fewer;
+            }
+        }
+
+        Self {
+            clock_multiplier: context.clock_multiplier,
+            mux_width: context.mux_width,
+            slots_used: routing.slots,
+            mux_full,
+            uncovered: uncovered
+                .iter()
+                .map(|&e| Self::report(context, e))
+                .collect(),
+            unrouted,
+            replacements: moves,
+            remaining: remaining
+                .iter()
+                .map(|&e| context.edges_array[e])
+                .collect(),
+        }
+    }
+
+    /**
+     *  Report for the edge, where the candidate MUX inputs are those of each
+     *  unit that contains one node of the edge, that are in the other MUX, as
+     *  these could be replaced by the other node.
+     */
+    fn report(context: &Context, edge: usize) -> EdgeReport {
+        let (a, b) = context.edges_array[edge];
+        let (units_a, units_b) =
+            (node_units(context, a), node_units(context, b));
+        let (ptrs, idxs) = context.find_edge_units();
+
+        let mut slots = Vec::new();
+        for (&(unit, b_mux), next) in units_a
+            .iter()
+            .map(|x| (x, b))
+            .chain(units_b.iter().map(|x| (x, a)))
+        {
+            if node_units(context, next).iter().any(|x| x.0 == unit) {
+                continue;
+            }
+            let other = if b_mux {
+                &context.a_mux_array[unit]
+            } else {
+                &context.b_mux_array[unit]
+            };
+            for (index, &node) in other.iter().enumerate() {
+                slots.push(MuxSlot {
+                    unit,
+                    b_mux: !b_mux,
+                    index,
+                    node,
+                    next,
+                    removable: context.is_removable(unit, !b_mux, node),
+                });
+            }
+        }
+
+        EdgeReport {
+            edge: (a, b),
+            units_a,
+            units_b,
+            slots,
+            routable: idxs[ptrs[edge]..ptrs[edge + 1]].to_vec(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.uncovered.is_empty() && self.unrouted.is_empty()
+    }
+
+    /**
+     *  Describes the unit, where a node is in the indicated MUX, along with its
+     *  used time-slots, and whether the other MUX has room for another input.
+     */
+    fn unit(&self, unit: usize, b_mux: bool) -> String {
+        let mux = if b_mux { 'B' } else { 'A' };
+        let (a_full, b_full) = self.mux_full[unit];
+        let room = if b_mux { !a_full } else { !b_full };
+        let used = self.slots_used[unit];
+        format!(
+            "COR{} ({}-MUX, {}/{} slots{}{})",
+            unit,
+            mux,
+            used,
+            self.clock_multiplier,
+            if used >= self.clock_multiplier {
+                ", full"
+            } else {
+                ""
+            },
+            if room { ", other MUX has room" } else { "" },
+        )
+    }
+}
+
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "All visibilities are computed");
+        }
+        let units = |xs: &[(usize, bool)]| -> String {
+            if xs.is_empty() {
+                return "no units".to_string();
+            }
+            let xs: Vec<String> =
+                xs.iter().map(|&(u, b)| self.unit(u, b)).collect();
+            xs.join(", ")
+        };
+
+        for r in self.uncovered.iter() {
+            let (a, b) = r.edge;
+            writeln!(f, "Uncovered visibility {} -> {}:", a, b)?;
+            writeln!(f, "    antenna {} in: {}", a, units(&r.units_a))?;
+            writeln!(f, "    antenna {} in: {}", b, units(&r.units_b))?;
+            let removable: Vec<String> = r
+                .slots
+                .iter()
+                .filter(|s| s.removable)
+                .map(|s| {
+                    let mux = if s.b_mux { 'B' } else { 'A' };
+                    format!(
+                        "COR{} {}-MUX[{}]: {} -> {}",
+                        s.unit, mux, s.index, s.node, s.next
+                    )
+                })
+                .collect();
+            if r.slots.is_empty()
+                && !r.units_a.is_empty()
+                && !r.units_b.is_empty()
+            {
+                writeln!(
+                    f,
+                    "    both antennas share a MUX, in each of their units"
+                )?;
+            } else if removable.is_empty() {
+                writeln!(
+                    f,
+                    "    none of the {} candidate MUX inputs are removable",
+                    r.slots.len()
+                )?;
+            } else {
+                writeln!(f, "    removable: {}", removable.join(", "))?;
+            }
+        }
+
+        for r in self.unrouted.iter() {
+            let (a, b) = r.edge;
+            let xs: Vec<String> = r
+                .routable
+                .iter()
+                .map(|&u| {
+                    format!(
+                        "COR{} ({}/{} slots)",
+                        u, self.slots_used[u], self.clock_multiplier
+                    )
+                })
+                .collect();
+            writeln!(
+                f,
+                "Unrouted visibility {} -> {}: all time-slots used, in: {}",
+                a,
+                b,
+                xs.join(", ")
+            )?;
+        }
+
+        if !self.replacements.is_empty() {
+            writeln!(f, "Suggested replacements:")?;
+            for r in self.replacements.iter() {
+                writeln!(f, "    {}", r)?;
+            }
+        }
+        if !self.remaining.is_empty() {
+            let xs: Vec<String> = self
+                .remaining
+                .iter()
+                .map(|(a, b)| format!("{} -> {}", a, b))
+                .collect();
+            writeln!(
+                f,
+                "Not covered by replacements (try '--auto'): {}",
+                xs.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,389 @@
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::context::Context;
+use crate::widths::BitWidths;
+
+/**
+ * Per-family FPGA costs, used to convert the (architectural) resource counts
+ * of a correlator configuration into device resources, and rough timing.
+ *
+ * Tables can be loaded from YAML files, so that additional FPGA families can
+ * be evaluated without changing the estimator.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostTable {
+    pub family: String,
+    pub device: String,
+
+    /* Logic resources */
+    pub lut_inputs: usize,
+    pub luts: usize,
+    pub flip_flops: usize,
+
+    /* Block SRAMs, and the widest port, in bits */
+    pub bram_bits: usize,
+    pub bram_width: usize,
+    pub brams: usize,
+
+    /* Rough timing, in nanoseconds */
+    pub clock_to_out: f64,
+    pub setup: f64,
+    pub lut_delay: f64,
+    pub route_delay: f64,
+    pub carry_delay: f64,
+}
+
+impl CostTable {
+    /**
+     *  Gowin GW2A-18 (LittleBee Arora), as used by the Tang Primer 20K.
+     */
+    pub fn gowin_gw2a() -> Self {
+        Self {
+            family: "GW2A".to_string(),
+            device: "GW2A-LV18PG256C8/I7".to_string(),
+
+            lut_inputs: 4,
+            luts: 20736,
+            flip_flops: 15552,
+
+            bram_bits: 18432,
+            bram_width: 36,
+            brams: 46,
+
+            clock_to_out: 0.4,
+            setup: 0.2,
+            lut_delay: 0.6,
+            route_delay: 0.9,
+            carry_delay: 0.06,
+        }
+    }
+
+    /**
+     *  Look up a built-in cost table, by FPGA family name.
+     */
+    pub fn from_family(family: &str) -> Option<Self> {
+        match family.to_lowercase().as_str() {
+            "gw2a" | "gowin" | "tang-primer-20k" => Some(Self::gowin_gw2a()),
+            _ => {
+                error!("Unknown FPGA family: '{}'", family);
+                None
+            }
+        }
+    }
+
+    /**
+     *  Load a cost table from a YAML file.
+     */
+    pub fn from_yaml(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let file = std::fs::File::open(path)?;
+        let table: Self = serde_yaml::from_reader(file)?;
+        table.validate()?;
+        Ok(table)
+    }
+
+    /**
+     *  Check that the LUTs have at least two inputs, and that the SRAM
+     *  geometry, and the delays, are positive.
+     */
+    pub fn validate(&self) -> Result<(), String> {
+        if self.lut_inputs < 2 {
+            return Err(format!(
+                "Cost table '{}' has too few LUT inputs ({})",
+                self.family, self.lut_inputs
+            ));
+        }
+        if self.bram_width == 0 || self.bram_bits == 0 {
+            return Err(format!(
+                "Cost table '{}' has an empty SRAM ({} x {} bits)",
+                self.family, self.bram_bits, self.bram_width
+            ));
+        }
+        let delays = [
+            ("clock_to_out", self.clock_to_out),
+            ("setup", self.setup),
+            ("lut_delay", self.lut_delay),
+            ("route_delay", self.route_delay),
+            ("carry_delay", self.carry_delay),
+        ];
+        for (name, delay) in delays {
+            if delay.is_nan() || delay <= 0.0 {
+                return Err(format!(
+                    "Cost table '{}' has a non-positive '{}' ({})",
+                    self.family, name, delay
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     *  Number of LUTs (in a tree) for a function with the given number of
+     *  inputs.
+     */
+    pub fn luts_for(&self, inputs: usize) -> usize {
+        if inputs <= self.lut_inputs {
+            return 1;
+        }
+        (inputs - 1).div_ceil(self.lut_inputs - 1)
+    }
+
+    /**
+     *  Depth of the LUT-tree for a function with the given number of inputs.
+     */
+    pub fn levels_for(&self, inputs: usize) -> usize {
+        let mut levels = 1;
+        let mut reach = self.lut_inputs;
+        while reach < inputs {
+            reach *= self.lut_inputs;
+            levels += 1;
+        }
+        levels
+    }
+
+    /**
+     *  Number of block SRAMs required for the given SRAM geometry.
+     */
+    pub fn brams_for(&self, words: usize, width: usize) -> usize {
+        if words == 0 || width == 0 {
+            return 0;
+        }
+        let depth = (self.bram_bits / self.bram_width).max(1);
+        width.div_ceil(self.bram_width) * words.div_ceil(depth)
+    }
+}
+
+/**
+ *  Number of bits to index 'n' items, as per Verilog's `$clog2(n)`.
+ */
+pub fn clog2(n: usize) -> usize {
+    if n <= 1 {
+        0
+    } else {
+        (usize::BITS - (n - 1).leading_zeros()) as usize
+    }
+}
+
+/**
+ * Resource and timing estimates for a correlator configuration, following the
+ * `correlator_24x_radio` architecture: a `sigbuffer`, and then per-unit, a
+ * `sigsource` (A- & B- MUXs), a `correlate`, and a `visaccum`, followed by a
+ * single (time-shared) `accumulator`.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Estimate {
+    pub family: String,
+    pub num_units: usize,
+
+    /* Architectural counts */
+    pub mux_inputs: usize,
+    pub select_bits: usize,
+    pub adders: usize,
+    pub adder_bits: usize,
+    pub accum_bits: usize,
+    pub buffer_words: usize,
+    pub buffer_bits: usize,
+    pub sram_bits: usize,
+
+    /* Device resources */
+    pub luts: usize,
+    pub flip_flops: usize,
+    pub brams: usize,
+    pub utilisation: (f64, f64, f64),
+
+    /* Timing */
+    pub mux_levels: usize,
+    pub critical_path: f64,
+    pub fmax: f64,
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (lu, fu, bu) = self.utilisation;
+        writeln!(f, "Estimate ({}) {{", self.family)?;
+        writeln!(f, "    num_units: {}", self.num_units)?;
+        writeln!(f, "    mux_inputs: {}", self.mux_inputs)?;
+        writeln!(f, "    select_bits: {}", self.select_bits)?;
+        writeln!(f, "    adders: {}", self.adders)?;
+        writeln!(f, "    adder_bits: {}", self.adder_bits)?;
+        writeln!(f, "    accum_bits: {}", self.accum_bits)?;
+        writeln!(f, "    buffer_words: {}", self.buffer_words)?;
+        writeln!(f, "    buffer_bits: {}", self.buffer_bits)?;
+        writeln!(f, "    sram_bits: {}", self.sram_bits)?;
+        writeln!(f, "    luts: {}\t({:.1}%)", self.luts, lu)?;
+        writeln!(f, "    flip_flops: {}\t({:.1}%)", self.flip_flops, fu)?;
+        writeln!(f, "    brams: {}\t({:.1}%)", self.brams, bu)?;
+        writeln!(f, "    mux_levels: {}", self.mux_levels)?;
+        writeln!(f, "    critical_path: {:.2} ns", self.critical_path)?;
+        writeln!(f, "    fmax: {:.1} MHz", self.fmax)?;
+        writeln!(f, "}}")
+    }
+}
+
+impl Estimate {
+    pub fn new(
+        context: &Context,
+        widths: &BitWidths,
+        table: &CostTable,
+    ) -> Self {
+        let units = context.num_units;
+        let trate = context.clock_multiplier;
+        let mux_n = context.mux_width;
+        let xbits = clog2(mux_n).max(1);
+        let tbits = clog2(trate).max(1);
+
+        // -- Per-unit 'sigsource' -- //
+
+        // Each A-/B- MUX input carries both I & Q bits, and the MUX-selects
+        // are stored in 'TRATE'-entry ROMs. The taps of the narrower MUX are
+        // padded to 'MUX_N', but the padding is never selected, so is assumed
+        // to be removed by synthesis.
+        let (a_n, b_n) = (context.a_width(), context.b_width());
+        let mux_inputs = units * (a_n + b_n);
+        let select_bits = units * 2 * trate * xbits;
+
+        // The 'valid', 'first', 'next', & 'last' strobes, and then their
+        // registered outputs, and the 'ai_o', 'aq_o', 'bi_o', & 'bq_o' outputs
+        const SRC_FLAGS: usize = 4 + 4 + 4;
+
+        let mux_luts = units
+            * 2
+            * (table.luts_for(a_n + xbits) + table.luts_for(b_n + xbits));
+        let rom_luts = units * 2 * xbits * table.luts_for(tbits);
+        let mux_ffs = units * (2 * (a_n + b_n) + 2 * xbits + SRC_FLAGS);
+
+        // -- Per-unit 'correlate' and 'visaccum' -- //
+
+        // The 1-bit products are decoded by a LUT per component, and then
+        // summed by the (Re & Im) 'ABITS' adders. Then the 'visaccum' adds
+        // these into 'SBITS' partial-sums, stored in 'LOOP0'-word SRAMs.
+        //
+        // Each product is decoded from '{ai, aq, bi, bq}'
+        const PRODUCT_BITS: usize = 4;
+        // Registers 'xr_r' & 'xi_r' (2-bit products), 'vld_r', 'fst_r', &
+        // 'lst_r', and then 'valid' & 'frame', of 'correlate'
+        const COR_FLAGS: usize = 2 * 2 + 3 + 2;
+        // Strobes of the read ('rd_cyc', 'rd_vld', 'pwrap'), accumulate
+        // ('czero', 'ac_cyc', 'ac_vld', 'ac_fst', 'ac_lst', 'wr_en'), and
+        // write ('wr_cyc', 'wr_vld', 'wr_fst', 'wr_lst') stages of 'visaccum'
+        const ACC_FLAGS: usize = 3 + 6 + 4;
+        // The 'rr_sum', 'ar_sum', & 'wr_dat' registers (and their Im parts)
+        const ACC_WORDS: usize = 3 * 2;
+        // Read, accumulate, and write SRAM-addresses, of 'visaccum'
+        const ACC_ADDRS: usize = 3;
+
+        let cor_luts =
+            units * (2 * table.luts_for(PRODUCT_BITS) + 2 * widths.abits);
+        let cor_ffs = units * (COR_FLAGS + 2 * widths.abits);
+        let acc_luts = units * (2 * widths.sbits + 2 * widths.sbits);
+        let acc_ffs = units
+            * (ACC_WORDS * widths.sbits
+                + 2 * widths.abits
+                + ACC_FLAGS
+                + ACC_ADDRS * clog2(widths.loop0)
+                + clog2(widths.loop1));
+        let psums_bits = units * widths.loop0 * 2 * widths.sbits;
+
+        // -- Final 'accumulator' -- //
+
+        // One time-shared adder (per component), with an SRAM that stores the
+        // partial visibilities for each of the 'CORES * TRATE' calculations.
+        //
+        // The 'r_dat', 'r_acc', & 'revis' registers (and their Im parts)
+        const FIN_WORDS: usize = 3 * 2;
+        // Registers 'czero', 'accum', 'write', 'wlast', 'valid', & 'rlast'
+        const FIN_FLAGS: usize = 6;
+
+        let pairs = units * trate;
+        let fin_luts = 2 * widths.accum + 2 * widths.accum;
+        let fin_ffs = FIN_WORDS * widths.accum
+            + 2 * widths.sbits
+            + widths.cbits
+            + 2 * clog2(pairs)
+            + FIN_FLAGS;
+        let vis_bits = pairs * 2 * widths.accum;
+
+        // -- Signal buffer ('sigbuffer') -- //
+
+        // The buffer has 'WORDS = 1 << (CBITS + BBITS)' entries, with 'BBITS'
+        // of one, for each of the I- & Q- SRAMs.
+        //
+        // Registers 'switch', 'start', 'fired', 'ended', 'valid', 'first',
+        // 'next', 'emit', 'last', 'frame', & 'tstep', of 'sigbuffer'
+        const BUF_FLAGS: usize = 11;
+
+        let cbits = clog2(widths.count());
+        let buffer_words = 1 << (cbits + 1);
+        let buffer_bits = buffer_words * 2 * context.num_antennas;
+        let buf_ffs =
+            2 * context.num_antennas + 2 * (cbits + 1) + tbits + BUF_FLAGS;
+
+        // The Re & Im adders of each 'correlate' and 'visaccum', and of the
+        // final 'accumulator'
+        let adders = units * (2 + 2) + 2;
+        let adder_bits = units * 2 * (widths.abits + widths.sbits);
+        let accum_bits = 2 * widths.accum;
+
+        let luts = mux_luts + rom_luts + cor_luts + acc_luts + fin_luts;
+        let flip_flops = mux_ffs + cor_ffs + acc_ffs + fin_ffs + buf_ffs;
+        let brams = table.brams_for(buffer_words, 2 * context.num_antennas)
+            + table.brams_for(pairs, 2 * widths.accum);
+        let sram_bits = buffer_bits + vis_bits + psums_bits;
+
+        let percent = |x: usize, y: usize| {
+            if y > 0 {
+                100.0 * x as f64 / y as f64
+            } else {
+                0.0
+            }
+        };
+        let utilisation = (
+            percent(luts, table.luts),
+            percent(flip_flops, table.flip_flops),
+            percent(brams, table.brams),
+        );
+
+        // -- Timing -- //
+
+        // Candidate critical paths are: the (registered) MUXs, and the carry-
+        // chains of the widest adder.
+        let mux_levels = table.levels_for(mux_n + xbits);
+        let mux_path =
+            mux_levels as f64 * (table.lut_delay + table.route_delay);
+        let add_path = table.lut_delay
+            + table.route_delay
+            + widths.accum.max(widths.sbits) as f64 * table.carry_delay;
+        let critical_path =
+            table.clock_to_out + table.setup + mux_path.max(add_path);
+        let fmax = 1000.0 / critical_path;
+
+        Self {
+            family: table.family.clone(),
+            num_units: units,
+            mux_inputs,
+            select_bits,
+            adders,
+            adder_bits,
+            accum_bits,
+            buffer_words,
+            buffer_bits,
+            sram_bits,
+            luts,
+            flip_flops,
+            brams,
+            utilisation,
+            mux_levels,
+            critical_path,
+            fmax,
+        }
+    }
+
+    /**
+     *  Check that the estimated timing meets the correlator clock-frequency,
+     *  given in MHz.
+     */
+    pub fn meets_timing(&self, clock: f64) -> bool {
+        self.fmax >= clock
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::VecDeque;
+
+/**
+ * Flow network, for computing minimum-cost maximum flows, using successive
+ * shortest paths.
+ *
+ * Each arc is stored alongside its (residual) reverse arc, so that arc 'i' has
+ * the reverse arc 'i ^ 1'.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct FlowNetwork {
+    heads: Vec<Vec<usize>>,
+    arcs: Vec<Arc>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Arc {
+    to: usize,
+    capacity: usize,
+    residual: usize,
+    cost: i64,
+}
+
+impl FlowNetwork {
+    pub fn new(nodes: usize) -> Self {
+        Self {
+            heads: vec![Vec::new(); nodes],
+            arcs: Vec::new(),
+        }
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.heads.len()
+    }
+
+    /**
+     *  Adds an arc with the given capacity, and returns its index.
+     */
+    pub fn add_arc(
+        &mut self,
+        from: usize,
+        to: usize,
+        capacity: usize,
+    ) -> usize {
+        self.add_costed_arc(from, to, capacity, 0)
+    }
+
+    /**
+     *  Adds an arc with the given capacity, and cost per unit of flow, and
+     *  returns its index.
+     */
+    pub fn add_costed_arc(
+        &mut self,
+        from: usize,
+        to: usize,
+        capacity: usize,
+        cost: i64,
+    ) -> usize {
+        let index = self.arcs.len();
+        self.arcs.push(Arc {
+            to,
+            capacity,
+            residual: capacity,
+            cost,
+        });
+        self.arcs.push(Arc {
+            to: from,
+            capacity: 0,
+            residual: 0,
+            cost: -cost,
+        });
+        self.heads[from].push(index);
+        self.heads[to].push(index + 1);
+        index
+    }
+
+    /**
+     *  Current flow along the arc.
+     */
+    pub fn flow(&self, arc: usize) -> usize {
+        let arc = &self.arcs[arc];
+        arc.capacity.saturating_sub(arc.residual)
+    }
+
+    /**
+     *  Computes the maximum flow, of minimum total cost, by augmenting along
+     *  the cheapest path (using Bellman-Ford, as the residual arcs have
+     *  negative costs) until no paths remain. Returns the flow and cost.
+     */
+    pub fn min_cost_flow(
+        &mut self,
+        source: usize,
+        sink: usize,
+    ) -> (usize, i64) {
+        let (mut total, mut cost) = (0, 0);
+        loop {
+            let mut dist = vec![i64::MAX; self.num_nodes()];
+            let mut prev = vec![usize::MAX; self.num_nodes()];
+            let mut queued = vec![false; self.num_nodes()];
+            let mut queue = VecDeque::from([source]);
+            dist[source] = 0;
+
+            while let Some(u) = queue.pop_front() {
+                queued[u] = false;
+                for &i in self.heads[u].iter() {
+                    let arc = self.arcs[i];
+                    if arc.residual > 0 && dist[u] + arc.cost < dist[arc.to] {
+                        dist[arc.to] = dist[u] + arc.cost;
+                        prev[arc.to] = i;
+                        if !queued[arc.to] {
+                            queued[arc.to] = true;
+                            queue.push_back(arc.to);
+                        }
+                    }
+                }
+            }
+            if dist[sink] == i64::MAX {
+                return (total, cost);
+            }
+
+            // Bottleneck of the path, and then push the flow along it
+            let mut pushed = usize::MAX;
+            let mut v = sink;
+            while v != source {
+                let i = prev[v];
+                pushed = pushed.min(self.arcs[i].residual);
+                v = self.arcs[i ^ 1].to;
+            }
+            let mut v = sink;
+            while v != source {
+                let i = prev[v];
+                self.arcs[i].residual -= pushed;
+                self.arcs[i ^ 1].residual += pushed;
+                v = self.arcs[i ^ 1].to;
+            }
+            total += pushed;
+            cost += pushed as i64 * dist[sink];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn min_cost_flow_of_network() {
+        let mut network = FlowNetwork::new(4);
+        network.add_costed_arc(0, 1, 2, 1);
+        network.add_costed_arc(0, 2, 1, 2);
+        let arc = network.add_costed_arc(1, 2, 1, 1);
+        let longer = network.add_costed_arc(1, 3, 1, 3);
+        network.add_costed_arc(2, 3, 2, 1);
+        assert_eq!(network.min_cost_flow(0, 3), (3, 10));
+        assert_eq!(network.flow(arc), 1);
+        assert_eq!(network.flow(longer), 1);
+    }
+}
@@ -0,0 +1,357 @@
+use std::f64::consts::PI;
+
+use crate::calibrate::Calibration;
+use crate::uvw::{AntennaArray, Pointing};
+
+/**
+ * A visibility, with its (u, v, w) in wavelengths.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Visibility {
+    pub uvw: [f64; 3],
+    pub re: f64,
+    pub im: f64,
+}
+
+/**
+ * Read visibilities from a CSV file, with a header-row, and with the 'a', 'b',
+ * 're', and 'im' columns, as written by 'test_vectors --vis', or by
+ * 'tart-decode --uvw' (when only the rows of 'frame' are used). The (u, v, w)
+ * are computed from the antenna positions, and signal-means are skipped. For
+ * dual-polarisation, only the 'XX' & 'YY' rows (of the 'pol' column) are used,
+ * so the image is of the total intensity.
+ *
+ * When calibrating, the visibilities are normalised by the auto-correlations
+ * (the rows where 'a == b'), and then Van Vleck corrected.
+ */
+pub fn read_visibilities(
+    source: &str,
+    array: &AntennaArray,
+    wavelength: f64,
+    pointing: &Pointing,
+    frame: usize,
+    calibration: Option<&Calibration>,
+) -> Result<Vec<Visibility>, String> {
+    let mut lines = source.lines().filter(|l| !l.trim().is_empty());
+    let header: Vec<&str> = lines
+        .next()
+        .ok_or("Empty visibilities file")?
+        .split(',')
+        .map(|x| x.trim())
+        .collect();
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|&h| h == name)
+            .ok_or(format!("Missing column: {}", name))
+    };
+    let (ca, cb, cr, ci) =
+        (column("a")?, column("b")?, column("re")?, column("im")?);
+    let cf = header.iter().position(|&h| h == "frame");
+    let cp = header.iter().position(|&h| h == "pol");
+
+    let mut rows = Vec::new();
+    for (i, line) in lines.enumerate() {
+        let fields: Vec<&str> = line.split(',').map(|x| x.trim()).collect();
+        let get = |c: usize| {
+            fields
+                .get(c)
+                .and_then(|x| x.parse::<f64>().ok())
+                .ok_or(format!("Line {}: invalid field {}", i + 2, c + 1))
+        };
+        if let Some(c) = cf {
+            if get(c)? as usize != frame {
+                continue;
+            }
+        }
+        if let Some(c) = cp {
+            if !matches!(fields.get(c), Some(&"XX") | Some(&"YY")) {
+                continue;
+            }
+        }
+        let (a, b) = (get(ca)? as usize, get(cb)? as usize);
+        if a.max(b) >= array.num_antennas() {
+            return Err(format!("Line {}: no position for antenna", i + 2));
+        }
+        rows.push((a, b, get(cr)?, get(ci)?));
+    }
+
+    let autos: Vec<Option<f64>> = (0..array.num_antennas())
+        .map(|n| rows.iter().find(|r| r.0 == n && r.1 == n).map(|r| r.2))
+        .collect();
+    let mut vis = Vec::new();
+    for &(a, b, re, im) in rows.iter().filter(|r| r.0 != r.1) {
+        let (re, im) = match calibration {
+            Some(cal) => match (autos[a], autos[b]) {
+                (Some(aa), Some(ab)) => cal.visibility((re, im), aa, ab),
+                _ => {
+                    return Err(format!(
+                        "Calibration requires the auto-correlations of {} & {}",
+                        a, b
+                    ))
+                }
+            },
+            None => (re, im),
+        };
+        let uvw = pointing
+            .project(array.baseline(a, b))
+            .map(|x| x / wavelength);
+        vis.push(Visibility { uvw, re, im });
+    }
+    Ok(vis)
+}
+
+/**
+ * Model visibilities of a unit point source, at the direction-cosines (l, m),
+ * with 'V = exp(2 pi i (u l + v m + w (n - 1)))', for the baseline 'r_a - r_b'.
+ */
+pub fn point_source(uvws: &[[f64; 3]], l: f64, m: f64) -> Vec<Visibility> {
+    let n = (1.0 - l * l - m * m).max(0.0).sqrt();
+    uvws.iter()
+        .map(|&uvw| {
+            let phase =
+                2.0 * PI * (uvw[0] * l + uvw[1] * m + uvw[2] * (n - 1.0));
+            Visibility {
+                uvw,
+                re: phase.cos(),
+                im: phase.sin(),
+            }
+        })
+        .collect()
+}
+
+/**
+ * All-sky (dirty) image, of 'size x size' pixels, covering the direction-
+ * cosines '[-1, 1]', with north up and east to the left, and with the pixel
+ * '(size/2, size/2)' at the phase-centre. Pixels below the horizon are zero.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirtyImage {
+    pub size: usize,
+    pub pixels: Vec<f64>,
+}
+
+impl DirtyImage {
+    pub fn cell(&self) -> f64 {
+        2.0 / self.size as f64
+    }
+
+    /**
+     *  Direction-cosines (l, m) of the pixel at column 'x', and row 'y'.
+     */
+    pub fn lm(&self, x: usize, y: usize) -> (f64, f64) {
+        let half = (self.size / 2) as f64;
+        let dl = self.cell();
+        ((half - x as f64) * dl, (half - y as f64) * dl)
+    }
+
+    fn above_horizon(&self, x: usize, y: usize) -> bool {
+        let (l, m) = self.lm(x, y);
+        l * l + m * m <= 1.0
+    }
+
+    /**
+     *  Direct Fourier transform, of each visibility and its conjugate, so this
+     *  is exact, but with a cost that is proportional to the number of pixels
+     *  times the number of visibilities.
+     */
+    pub fn dft(vis: &[Visibility], size: usize) -> Self {
+        let mut image = Self {
+            size,
+            pixels: vec![0.0; size * size],
+        };
+        let scale = 1.0 / vis.len().max(1) as f64;
+        for y in 0..size {
+            for x in 0..size {
+                if !image.above_horizon(x, y) {
+                    continue;
+                }
+                let (l, m) = image.lm(x, y);
+                let sum: f64 = vis
+                    .iter()
+                    .map(|v| {
+                        let phase = -2.0 * PI * (v.uvw[0] * l + v.uvw[1] * m);
+                        v.re * phase.cos() - v.im * phase.sin()
+                    })
+                    .sum();
+                image.pixels[y * size + x] = sum * scale;
+            }
+        }
+        image
+    }
+
+    /**
+     *  Nearest-neighbour gridding of each visibility (and its conjugate), and
+     *  then an FFT, where 'size' must be a power of two. Visibilities beyond
+     *  the edge of the (u, v) grid are dropped.
+     */
+    pub fn gridded(vis: &[Visibility], size: usize) -> Result<Self, String> {
+        if !size.is_power_of_two() {
+            return Err(format!("Image size must be a power of two: {}", size));
+        }
+        let du = 1.0 / 2.0; // For the field-of-view, of width 2
+        let half = (size / 2) as i64;
+        let mut grid = vec![(0.0, 0.0); size * size];
+
+        for v in vis.iter() {
+            for (sign, im) in [(1.0, v.im), (-1.0, -v.im)] {
+                let k = (sign * v.uvw[0] / du).round() as i64;
+                let j = (sign * v.uvw[1] / du).round() as i64;
+                if k.abs() >= half || j.abs() >= half {
+                    continue;
+                }
+                // Shifts the phase-centre to the middle of the image
+                let flip = if (k + j) % 2 == 0 { 1.0 } else { -1.0 };
+                let index = (j.rem_euclid(size as i64) as usize) * size
+                    + k.rem_euclid(size as i64) as usize;
+                grid[index].0 += flip * v.re;
+                grid[index].1 += flip * im;
+            }
+        }
+
+        // Inverse transform, of the rows and then the columns
+        for row in grid.chunks_exact_mut(size) {
+            fft(row, true);
+        }
+        let mut column = vec![(0.0, 0.0); size];
+        for x in 0..size {
+            for y in 0..size {
+                column[y] = grid[y * size + x];
+            }
+            fft(&mut column, true);
+            for y in 0..size {
+                grid[y * size + x] = column[y];
+            }
+        }
+
+        let mut image = Self {
+            size,
+            pixels: vec![0.0; size * size],
+        };
+        let scale = 1.0 / (2 * vis.len().max(1)) as f64;
+        for y in 0..size {
+            for x in 0..size {
+                if image.above_horizon(x, y) {
+                    image.pixels[y * size + x] = grid[y * size + x].0 * scale;
+                }
+            }
+        }
+        Ok(image)
+    }
+
+    /**
+     *  The brightest pixel, as (l, m, value).
+     */
+    pub fn peak(&self) -> (f64, f64, f64) {
+        let (index, value) = self.pixels.iter().enumerate().fold(
+            (0, f64::MIN),
+            |(i, m), (j, &p)| {
+                if p > m {
+                    (j, p)
+                } else {
+                    (i, m)
+                }
+            },
+        );
+        let (l, m) = self.lm(index % self.size, index / self.size);
+        (l, m, value)
+    }
+
+    /**
+     *  8-bit, binary (P5) PGM image, scaled to the range of the pixels.
+     */
+    pub fn to_pgm(&self) -> Vec<u8> {
+        let lo = self.pixels.iter().cloned().fold(f64::MAX, f64::min);
+        let hi = self.pixels.iter().cloned().fold(f64::MIN, f64::max);
+        let range = if hi > lo { hi - lo } else { 1.0 };
+
+        let mut out =
+            format!("P5\n{} {}\n255\n", self.size, self.size).into_bytes();
+        out.extend(
+            self.pixels
+                .iter()
+                .map(|&p| ((p - lo) / range * 255.0).round() as u8),
+        );
+        out
+    }
+
+    /**
+     *  FITS image, of 32-bit floats, with the (l, m) axes, and where the first
+     *  row is the southern-most.
+     */
+    pub fn to_fits(&self) -> Vec<u8> {
+        let centre = (self.size / 2 + 1) as f64;
+        let cards = [
+            fits_card("SIMPLE", "T"),
+            fits_card("BITPIX", "-32"),
+            fits_card("NAXIS", "2"),
+            fits_card("NAXIS1", &self.size.to_string()),
+            fits_card("NAXIS2", &self.size.to_string()),
+            fits_card("CTYPE1", "'L       '"),
+            fits_card("CRPIX1", &format!("{:.1}", centre)),
+            fits_card("CRVAL1", "0.0"),
+            fits_card("CDELT1", &format!("{:E}", -self.cell())),
+            fits_card("CTYPE2", "'M       '"),
+            fits_card("CRPIX2", &format!("{:.1}", centre)),
+            fits_card("CRVAL2", "0.0"),
+            fits_card("CDELT2", &format!("{:E}", self.cell())),
+            fits_card("BUNIT", "'JY/BEAM '"),
+            format!("{:80}", "END"),
+        ];
+        let mut out = cards.concat().into_bytes();
+        out.resize(out.len().div_ceil(2880) * 2880, b' ');
+
+        for row in self.pixels.chunks_exact(self.size).rev() {
+            for &p in row.iter() {
+                out.extend((p as f32).to_be_bytes());
+            }
+        }
+        out.resize(out.len().div_ceil(2880) * 2880, 0);
+        out
+    }
+}
+
+/**
+ * Fixed-format header card, where strings are left-justified, and other values
+ * are right-justified to column 30.
+ */
+fn fits_card(key: &str, value: &str) -> String {
+    if value.starts_with('\'') {
+        format!("{:8}= {:<70}", key, value)
+    } else {
+        format!("{:8}= {:>20}{:50}", key, value, "")
+    }
+}
+
+/**
+ * In-place, radix-2 FFT, of (re, im) values, and without normalisation, where
+ * the inverse transform uses the positive exponent.
+ */
+pub fn fft(data: &mut [(f64, f64)], inverse: bool) {
+    let n = data.len();
+    let bits = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if j > i {
+            data.swap(i, j);
+        }
+    }
+
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut len = 2;
+    while len <= n {
+        let (ws, wc) = (sign * 2.0 * PI / len as f64).sin_cos();
+        for start in (0..n).step_by(len) {
+            let (mut wr, mut wi) = (1.0, 0.0);
+            for k in 0..len / 2 {
+                let (ar, ai) = data[start + k];
+                let (br, bi) = data[start + k + len / 2];
+                let (tr, ti) = (br * wr - bi * wi, br * wi + bi * wr);
+                data[start + k] = (ar + tr, ai + ti);
+                data[start + k + len / 2] = (ar - tr, ai - ti);
+                (wr, wi) = (wr * wc - wi * ws, wr * ws + wi * wc);
+            }
+        }
+        len <<= 1;
+    }
+}
@@ -1,9 +1,39 @@
 #![allow(unused)]
+pub use crate::auto::*;
+pub use crate::calibrate::*;
 pub use crate::chunked::*;
 pub use crate::context::*;
+pub use crate::decode::*;
+pub use crate::diagnose::*;
+pub use crate::estimate::*;
+pub use crate::flow::*;
+pub use crate::imaging::*;
 pub use crate::means::*;
+pub use crate::pairs::*;
+pub use crate::polarisation::*;
+pub use crate::refine::*;
+pub use crate::sigsource::*;
+pub use crate::solution::*;
+pub use crate::top::*;
+pub use crate::uvw::*;
+pub use crate::widths::*;
 
+pub mod auto;
+pub mod calibrate;
 pub mod chunked;
 pub mod context;
+pub mod decode;
+pub mod diagnose;
+pub mod estimate;
+pub mod flow;
+pub mod imaging;
 pub mod logger;
 pub mod means;
+pub mod pairs;
+pub mod polarisation;
+pub mod refine;
+pub mod sigsource;
+pub mod solution;
+pub mod top;
+pub mod uvw;
+pub mod widths;
@@ -44,6 +44,6 @@ fn colour_format(
         style(level).paint(rec.level().to_string()),
         rec.file().unwrap_or("<unknown>"),
         rec.line().unwrap_or(0),
-        style(level).paint(&rec.args().to_string())
+        style(level).paint(rec.args().to_string())
     )
 }
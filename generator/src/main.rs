@@ -1,6 +1,9 @@
 use clap::Parser;
-use log::{debug, warn};
-use tart_dsp::{logger, Chunked, Context};
+use log::warn;
+use tart_dsp::{
+    logger, AutoSolve, BitWidths, Chunked, Context, CostTable, Diagnosis,
+    Estimate, LegacyPairs, Solution, TopLevel, LEGACY_BLOCKS,
+};
 
 /// Command line options for configuring the TART DSP, based on the number of
 /// antennas, and the relative frequencies of the antenna source signals, vs
@@ -38,6 +41,107 @@ struct Args {
     #[arg(short, long, value_name = "BITS", default_value = "0")]
     extra_bits: usize,
 
+    /// Compute only the visibilities of this (YAML or JSON) list of antenna-
+    /// pairs, instead of all of them
+    #[arg(long, value_name = "FILE")]
+    edges: Option<String>,
+
+    /// Antennas have X- & Y- feeds, and compute the XX, YY, XY, and YX products
+    #[arg(long)]
+    dual_pol: bool,
+
+    /// Number of correlator units [default: derived from the calculations]
+    #[arg(long, value_name = "NUM")]
+    units: Option<usize>,
+
+    /// Number of A-MUX inputs [default: derived MUX-width]
+    #[arg(long, value_name = "NUM")]
+    a_width: Option<usize>,
+
+    /// Number of B-MUX inputs [default: derived MUX-width]
+    #[arg(long, value_name = "NUM")]
+    b_width: Option<usize>,
+
+    /// Dead antennas, whose visibilities and signal-means are not computed
+    #[arg(long, value_name = "LIST", value_delimiter = ',')]
+    dead: Vec<usize>,
+
+    /// Correlator units (e.g., faulty tiles) that are given no assignments
+    #[arg(long, value_name = "LIST", value_delimiter = ',')]
+    disable_units: Vec<usize>,
+
+    /// Escalate (refine, widen the MUXs, then add units) until a solution
+    #[arg(long, conflicts_with_all = ["units", "a_width", "b_width"])]
+    auto: bool,
+
+    /// Number of ADC bits, for each of the I- & Q- signal components (1 or 2)
+    #[arg(long, value_name = "BITS", default_value = "1")]
+    adc_bits: usize,
+
+    /// Number of samples in each first-stage partial-sum
+    #[arg(long, value_name = "NUM", default_value = "3")]
+    loop0: usize,
+
+    /// Number of first-stage partial-sums in each (second-stage) partial-sum
+    #[arg(long, value_name = "NUM", default_value = "5")]
+    loop1: usize,
+
+    /// Integration time, in seconds, for each set of visibilities
+    #[arg(long, value_name = "SECONDS", default_value = "1.0")]
+    integration: f64,
+
+    /// Sampling-rate of the radio signals, in Hz
+    #[arg(long, value_name = "HZ", default_value = "16384000")]
+    sample_rate: f64,
+
+    /// Number of partial-sums per visibility (overrides '--integration')
+    #[arg(long, value_name = "NUM")]
+    passes: Option<u64>,
+
+    /// First-stage adder bit-width of the hardware, to check for overflows
+    #[arg(long, value_name = "BITS")]
+    abits: Option<usize>,
+
+    /// Partial-sums bit-width of the hardware, to check for overflows
+    #[arg(long, value_name = "BITS")]
+    sbits: Option<usize>,
+
+    /// Accumulator bit-width of the hardware, to check for overflows
+    #[arg(long, value_name = "BITS")]
+    accum: Option<usize>,
+
+    /// Estimate the FPGA resources and timing, for the configuration
+    #[arg(long)]
+    estimate: bool,
+
+    /// FPGA family, for the resource and timing estimates
+    #[arg(long, value_name = "NAME", default_value = "gw2a")]
+    family: String,
+
+    /// YAML cost-table file, used instead of the built-in FPGA families
+    #[arg(long, value_name = "FILE")]
+    cost_table: Option<String>,
+
+    /// Write the (YAML) diagnosis, of any visibilities that are not computed
+    #[arg(long, value_name = "FILE")]
+    diagnose: Option<String>,
+
+    /// Save the correlator solution (YAML) to this file
+    #[arg(long, value_name = "FILE")]
+    save: Option<String>,
+
+    /// Write the solution as (legacy TART2) 'PAIRSxx_yy' parameters to this file
+    #[arg(long, value_name = "FILE")]
+    pairs: Option<String>,
+
+    /// Write the correlator top-level module (Verilog) to this file
+    #[arg(long, value_name = "FILE")]
+    top: Option<String>,
+
+    /// Name of the top-level module [default: correlator_<N>x_radio]
+    #[arg(long, value_name = "NAME")]
+    top_name: Option<String>,
+
     /// Verbosity
     #[arg(short, long, value_name = "LEVEL")]
     log_level: Option<String>,
@@ -52,126 +156,184 @@ pub struct EdgesArray {
     pub edges: Chunked<usize>,
 }
 
-fn mux_selects(
-    context: Context,
-    edges: Chunked<usize>,
-    means: Chunked<(usize, usize)>,
-) -> Chunked<(usize, usize)> {
-    let edge_num = context.edges_array.len();
+/// Assign the correlator-pairs, and the self-means, to each correlator unit.
+fn assign_calculations(context: &mut Context) -> (String, Option<Solution>) {
+    let solution = Solution::solve(context);
+    let result = match &solution {
+        Some(solution) => describe_solution(solution),
+        None if context.is_complete() => "FAILED !!".to_string(),
+        None => String::new(),
+    };
+    (result, solution)
+}
 
-    // Create a LUT: Edge -> Core.
-    let mut edge_to_core: Vec<usize> = vec![usize::MAX; edge_num];
+/// The visibility- and signal-mean- calculation assignments, of the solution,
+/// along with the polarisations of each output, for dual-polarisation.
+fn describe_solution(solution: &Solution) -> String {
+    let mut lines = vec![
+        "Visibility-calculation assignments:".to_string(),
+        format!("{}", solution.edges),
+        "Signal-mean calculation assignments:".to_string(),
+        format!("{}", solution.means),
+        format!("{}", solution.slots),
+    ];
+    if solution.context.dual_pol {
+        lines.push("Output products:".to_string());
+        let trate = solution.context.clock_multiplier;
+        for (u, labels) in solution.output_labels().chunks(trate).enumerate() {
+            let xs: Vec<String> = labels
+                .iter()
+                .map(|x| match x {
+                    Some(p) => p.to_string(),
+                    None => "-".to_string(),
+                })
+                .collect();
+            lines.push(format!("    COR{}: {}", u, xs.join(", ")));
+        }
+    }
+    lines.join("\n")
+}
 
-    for (u, core) in edges.into_iter().enumerate() {
-        debug!("core[{}]: {:?}", u, core);
-        for &e in core.iter() {
-            edge_to_core[e] = u;
+/// Compute the accumulator bit-widths, and warn about any given (hardware) bit-
+/// widths that are too narrow.
+fn accumulator_widths(args: &Args) -> Option<BitWidths> {
+    let widths = match args.passes {
+        Some(passes) => {
+            BitWidths::new(args.adc_bits, args.loop0, args.loop1, passes)
+        }
+        None => BitWidths::from_time(
+            args.adc_bits,
+            args.loop0,
+            args.loop1,
+            args.integration,
+            args.sample_rate,
+        ),
+    };
+
+    if let Some(widths) = &widths {
+        for risk in widths.overflow_risks(args.abits, args.sbits, args.accum) {
+            warn!("Overflow risk: {}", risk);
         }
     }
+    widths
+}
 
-    // warn!("Edge -> Core:\n{:?}", edge_to_core);
+/// Load the cost-table file, or else the built-in table of the FPGA family.
+fn cost_table(args: &Args) -> Result<CostTable, Box<dyn std::error::Error>> {
+    match &args.cost_table {
+        Some(path) => CostTable::from_yaml(path),
+        None => Ok(CostTable::from_family(&args.family)
+            .ok_or("Unsupported FPGA family")?),
+    }
+}
 
-    let mut selects = Chunked::new(context.clock_multiplier, context.num_units);
+/// Estimate the FPGA resources and timing, for the given configuration.
+fn resource_estimate(
+    args: &Args,
+    context: &Context,
+    widths: &BitWidths,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let table = cost_table(args)?;
+    let estimate = Estimate::new(context, widths, &table);
 
-    for (u, (a_mux, b_mux)) in context
-        .a_mux_array
-        .into_iter()
-        .zip(context.b_mux_array.into_iter())
-        .enumerate()
-    {
-        let mut i: usize = 0;
-        let mut j: usize = 0;
-
-        while i < a_mux.len() && j < b_mux.len() {
-            let a = a_mux[i];
-            let b = b_mux[j];
-
-            if a < b {
-                for (k, &b) in b_mux.iter().enumerate().skip(j) {
-                    let e = context.calc_edge_index(a, b);
-                    if edge_to_core[e] == u {
-                        selects.push(u, (i, k));
-                    }
-                }
-                if i < a_mux.len() {
-                    i += 1;
-                }
-            } else {
-                for (k, &a) in a_mux.iter().enumerate().skip(i) {
-                    let e = context.calc_edge_index(a, b);
-                    if edge_to_core[e] == u {
-                        selects.push(u, (k, j));
-                    }
-                }
-                if j < b_mux.len() {
-                    j += 1;
-                }
-            }
-        }
+    let clock = args.sample_rate * args.multiplier as f64 * 1e-6;
+    if !estimate.meets_timing(clock) {
+        warn!(
+            "Estimated Fmax ({:.1} MHz) is below the correlator clock ({:.1} MHz)",
+            estimate.fmax, clock
+        );
     }
+    Ok(format!("{}", estimate))
+}
 
-    // fixme: does not work because 'Chunked<T>' does not allow duplicates!
-    for (u, pairs) in means.into_iter().enumerate() {
-        warn!("pairs[{}]: {:?}", u, pairs);
-        let a_mux = &context.a_mux_array[u];
-        let b_mux = &context.b_mux_array[u];
-
-        for (a, b) in pairs {
-            if let Ok(i) = a_mux.binary_search(a) {
-                if let Ok(j) = b_mux.binary_search(b) {
-                    selects.push(u, (i, j));
-                }
-            }
+/// The visibilities and signal-means that are no longer computed, due to dead
+/// antennas, and any disabled units.
+fn masks_report(context: &Context) -> Option<String> {
+    if context.dead_antennas.is_empty() && context.disabled_units.is_empty() {
+        return None;
+    }
+    let dropped: Vec<String> = context
+        .dropped_edges()
+        .into_iter()
+        .map(|e| {
+            let (a, b) = context.edges_array[e];
+            format!("{} -> {}", context.node_label(a), context.node_label(b))
+        })
+        .collect();
+    let dead: Vec<String> = context
+        .dead_antennas
+        .iter()
+        .map(|&n| context.node_label(n))
+        .collect();
+    let mut report = Vec::new();
+    if !context.dead_antennas.is_empty() {
+        report.push(format!("Dead antennas: [{}]", dead.join(", ")));
+        report.push(format!(
+            "Visibilities no longer produced (num = {}): {}",
+            dropped.len(),
+            dropped.join(", ")
+        ));
+        if !context.no_means {
+            report.push(format!(
+                "Signal-means no longer produced: [{}]",
+                dead.join(", ")
+            ));
         }
     }
-
-    selects
+    if !context.disabled_units.is_empty() {
+        report.push(format!(
+            "Disabled units (no assignments): {:?}",
+            context.disabled_units
+        ));
+    }
+    Some(report.join("\n") + "\n")
 }
 
-/// Assign the correlator-pairs, and the self-means, to each correlator unit.
-fn assign_calculations(context: &mut Context) -> String {
-    let mut result = Vec::new();
-    if let Some(edges) = context.assign_edges(true) {
-        result.push("Visibility-calculation assignments:".to_string());
-        result.push(format!("{}", edges));
-
-        result.push("Signal-mean calculation assignments:".to_string());
-        let means = if let Some(means) = context.assign_means(edges.clone()) {
-            result.push(format!("{}", means));
-            means
-        } else if let Some(means) = context.means_another(edges.clone()) {
-            result.push(format!("{}", means));
-            means
-        } else if let Some(means) = context.means_assign(edges.clone()) {
-            result.push(format!("{}", means));
-            means
-        } else {
-            result.push("FAILED !!".to_string());
-            Chunked::new(1, 0)
+/// Derive the configuration, for all of the antenna-pairs, or else for just
+/// the edges of the '--edges' file.
+fn derive_context(args: &Args) -> Result<Context, String> {
+    if args.dual_pol {
+        let edges = match &args.edges {
+            Some(path) => Some(Context::load_edges(path)?),
+            None => None,
         };
-        let selects = mux_selects(context.clone(), edges, means);
-        result.push(format!("{}", selects));
+        return Context::dual_pol(
+            args.antennas,
+            edges.as_deref(),
+            args.multiplier,
+            args.no_means,
+            args.extra_bits,
+        );
+    }
+    match &args.edges {
+        Some(path) => Context::with_edges(
+            args.antennas,
+            &Context::load_edges(path)?,
+            args.multiplier,
+            args.no_means,
+            args.extra_bits,
+        ),
+        None => Ok(Context::new(
+            args.antennas,
+            args.multiplier,
+            args.no_means,
+            args.extra_bits,
+        )),
     }
-    result.join("\n")
 }
 
-/**
- * Main entry-point into the TART DSP correlator-pairs assignment procedure.
- */
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    println!("TART DSP Generator Extreme\n");
-    let args: Args = Args::parse();
-    let level: String = args.log_level.unwrap_or("info".to_string());
-    logger::configure(level.as_str(), args.verbose > 0)?;
-
-    let mut context: Context = tart_dsp::Context::new(
-        args.antennas,
-        args.multiplier,
-        args.no_means,
-        args.extra_bits,
-    );
+/// Partition the derived configuration, and then assign the calculations.
+fn partition_and_assign(
+    args: &Args,
+) -> Result<(Context, Option<Solution>), String> {
+    let mut context: Context = derive_context(args)?
+        .with_masks(&args.dead, &args.disable_units)?
+        .with_overrides(args.units, args.a_width, args.b_width)?;
     context.partition(args.verbose > 0);
     println!("{}", context);
+    if let Some(report) = masks_report(&context) {
+        println!("{}", report);
+    }
 
     if args.verbose > 0 {
         if args.verbose > 2 {
@@ -195,5 +357,83 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
-    Ok(println!("{}", assign_calculations(&mut context)))
+    let (result, solution) = assign_calculations(&mut context);
+    println!("{}", result);
+    Ok((context, solution))
+}
+
+/**
+ * Main entry-point into the TART DSP correlator-pairs assignment procedure.
+ */
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("TART DSP Generator Extreme\n");
+    let args: Args = Args::parse();
+    let level: String = args.log_level.clone().unwrap_or("info".to_string());
+    logger::configure(level.as_str(), args.verbose > 0)?;
+
+    let mut auto = None;
+    let (context, solution) = if args.auto {
+        let context = derive_context(&args)?
+            .with_masks(&args.dead, &args.disable_units)?;
+        let solve = AutoSolve::new(context, args.extra_bits);
+        let context = match &solve.solution {
+            Some(solution) => solution.context.clone(),
+            None => solve.initial.clone(),
+        };
+        println!("{}", context);
+        if let Some(report) = masks_report(&context) {
+            println!("{}", report);
+        }
+        if let Some(solution) = &solve.solution {
+            println!("{}", describe_solution(solution));
+        }
+        println!("{}", solve);
+        let solution = solve.solution.clone();
+        auto = Some(solve);
+        (context, solution)
+    } else {
+        partition_and_assign(&args)?
+    };
+
+    if !solution.as_ref().is_some_and(|s| s.is_complete()) {
+        let diagnosis = Diagnosis::new(&context);
+        println!("{}", diagnosis);
+        if let Some(path) = &args.diagnose {
+            std::fs::write(path, serde_yaml::to_string(&diagnosis)?)?;
+            println!("Diagnosis written to: {}\n", path);
+        }
+    }
+    if let (Some(path), Some(solution)) = (&args.save, &solution) {
+        let file = std::fs::File::create(path)?;
+        serde_yaml::to_writer(file, solution)?;
+        println!("Solution saved to: {}\n", path);
+    }
+    if let (Some(path), Some(solution)) = (&args.pairs, &solution) {
+        let legacy = LegacyPairs::from_solution(solution, LEGACY_BLOCKS)?;
+        std::fs::write(path, legacy.to_verilog())?;
+        println!("PAIRS parameters written to: {}\n", path);
+    }
+    if let Some(widths) = accumulator_widths(&args) {
+        println!("{}", widths);
+        if args.estimate {
+            println!("{}", resource_estimate(&args, &context, &widths)?);
+        }
+        if let (Some(auto), Some(table)) = (&auto, cost_table(&args).ok()) {
+            if let Some(delta) = auto.cost_delta(&widths, &table) {
+                println!("Escalation cost ({}): {}\n", table.family, delta);
+            }
+        }
+        if let (Some(path), Some(solution)) = (&args.top, &solution) {
+            let name = args
+                .top_name
+                .clone()
+                .unwrap_or_else(|| TopLevel::default_name(solution));
+            let top = TopLevel::new(&name, solution, &widths);
+            std::fs::write(path, top.to_verilog())?;
+            println!("Top-level module '{}' written to: {}\n", name, path);
+        }
+    } else {
+        println!("Invalid accumulator settings!");
+    }
+    Ok(())
 }
@@ -0,0 +1,428 @@
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::chunked::Chunked;
+use crate::context::Context;
+use crate::estimate::clog2;
+use crate::sigsource::pack_hex;
+use crate::solution::{Slot, Solution};
+
+/**
+ *  Number of correlators in each block of `PAIRSxx_yy` parameters, as used by
+ *  `tart_24x_radio.v`.
+ */
+pub const LEGACY_BLOCKS: usize = 4;
+
+/**
+ * Antenna-pair tables of the (legacy) TART2 correlators, as stored within the
+ * `PAIRSxx_yy` parameters of `tart_24x_radio.v`.
+ *
+ * Each parameter holds the pairs for one correlator, with the pair for time-
+ * slice 't' packed into bits '[t*2*K+2*K-1:t*2*K]', as '{b[K-1:0], a[K-1:0]}',
+ * for 'K'-bit antenna indices. Correlator 'xx * blocks + yy' is given by the
+ * 'PAIRSxx_yy' parameter, and the last correlator(s) repeat some pairs, to
+ * fill their unused time-slices. The last block may have fewer correlators.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LegacyPairs {
+    pub num_antennas: usize,
+    pub index_bits: usize,
+    pub trate: usize,
+    pub blocks: usize,
+    pub units: Vec<Vec<(usize, usize)>>,
+}
+
+impl fmt::Display for LegacyPairs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "LegacyPairs {{")?;
+        writeln!(f, "    num_antennas: {}", self.num_antennas)?;
+        writeln!(f, "    index_bits: {}", self.index_bits)?;
+        writeln!(f, "    trate: {}", self.trate)?;
+        writeln!(f, "    blocks: {}", self.blocks)?;
+        writeln!(f, "    units: [")?;
+        for (u, pairs) in self.units.iter().enumerate() {
+            writeln!(f, "        {:02}: {:?}", u, pairs)?;
+        }
+        writeln!(f, "    ]")?;
+        writeln!(f, "}}")
+    }
+}
+
+/**
+ *  Unpack the (a, b) antenna-pairs, for each of the 'trate' time-slices, from a
+ *  hex parameter value.
+ */
+pub fn decode_pairs(
+    hex: &str,
+    trate: usize,
+    index_bits: usize,
+) -> Result<Vec<(usize, usize)>, String> {
+    let digits: Vec<u32> = hex
+        .chars()
+        .filter(|&c| c != '_')
+        .map(|c| c.to_digit(16).ok_or(format!("Invalid hex digit: '{}'", c)))
+        .collect::<Result<_, _>>()?;
+    let bit = |k: usize| -> usize {
+        let n = digits.len();
+        if k >> 2 >= n {
+            0
+        } else {
+            ((digits[n - 1 - (k >> 2)] >> (k & 3)) & 1) as usize
+        }
+    };
+    let field = |base: usize| -> usize {
+        (0..index_bits).fold(0, |x, j| x | (bit(base + j) << j))
+    };
+
+    Ok((0..trate)
+        .map(|t| {
+            let base = 2 * index_bits * t;
+            (field(base), field(base + index_bits))
+        })
+        .collect())
+}
+
+/**
+ *  Parse a `PAIRSxx_yy = {W'hXXXX}` parameter declaration, returning the
+ *  indices (xx, yy), the declared bit-width, and the hex digits.
+ */
+fn parse_parameter(line: &str) -> Option<(usize, usize, usize, &str)> {
+    let rest = &line[line.find("PAIRS")? + 5..];
+    let (name, rest) = rest.split_once('=')?;
+    let (xx, yy) = name.trim().split_once('_')?;
+    let (xx, yy) = (xx.parse().ok()?, yy.parse().ok()?);
+
+    let (width, rest) = rest.split_once("'h")?;
+    let width = width.trim_start_matches([' ', '{']).parse().ok()?;
+    let end = rest
+        .find(|c: char| !c.is_ascii_hexdigit() && c != '_')
+        .unwrap_or(rest.len());
+
+    Some((xx, yy, width, &rest[..end]))
+}
+
+impl LegacyPairs {
+    /**
+     *  Import all of the `PAIRSxx_yy` parameters from the (Verilog) source,
+     *  where each correlator has 'trate' time-slices.
+     */
+    pub fn parse(source: &str, trate: usize) -> Result<Self, String> {
+        let mut params = Vec::new();
+        for line in source.lines() {
+            let line = line.split("//").next().unwrap_or_default();
+            if !line.contains("parameter") {
+                continue;
+            }
+            if let Some(param) = parse_parameter(line) {
+                params.push(param);
+            }
+        }
+        if params.is_empty() {
+            return Err("No 'PAIRSxx_yy' parameters found".to_string());
+        }
+
+        let width = params[0].2;
+        if params.iter().any(|p| p.2 != width) || width % (2 * trate) != 0 {
+            return Err(format!(
+                "Parameter widths must all be {}, and a multiple of {}",
+                width,
+                2 * trate
+            ));
+        }
+        let index_bits = width / (2 * trate);
+        let blocks = params.iter().map(|p| p.1).max().unwrap_or_default() + 1;
+        let count = params
+            .iter()
+            .map(|p| p.0 * blocks + p.1)
+            .max()
+            .unwrap_or_default()
+            + 1;
+
+        let mut units: Vec<Option<Vec<(usize, usize)>>> = vec![None; count];
+        for &(xx, yy, _, hex) in params.iter() {
+            let pairs = decode_pairs(hex, trate, index_bits)?;
+            debug!("PAIRS{:02}_{:02}: {:?}", xx, yy, pairs);
+            units[xx * blocks + yy] = Some(pairs);
+        }
+
+        let units: Vec<Vec<(usize, usize)>> = units
+            .into_iter()
+            .enumerate()
+            .map(|(u, p)| {
+                p.ok_or(format!(
+                    "Missing parameter: PAIRS{:02}_{:02}",
+                    u / blocks,
+                    u % blocks
+                ))
+            })
+            .collect::<Result<_, _>>()?;
+
+        if let Some(&(a, b)) = units.iter().flatten().find(|(a, b)| a == b) {
+            return Err(format!("Invalid antenna-pair: ({}, {})", a, b));
+        }
+        let num_antennas = units
+            .iter()
+            .flatten()
+            .map(|&(a, b)| a.max(b) + 1)
+            .max()
+            .unwrap_or_default();
+
+        Ok(Self {
+            num_antennas,
+            index_bits,
+            trate,
+            blocks,
+            units,
+        })
+    }
+
+    /**
+     *  Export the visibility calculations of the solution, with 'blocks'
+     *  correlators per 'PAIRSxx_yy' block. Each idle time-slice repeats the
+     *  first pair of its correlator, and signal-means slots are not supported
+     *  by the legacy correlators, so are also treated as idle. A correlator
+     *  with no visibility calculations (e.g., a disabled unit) has no pair
+     *  to repeat, and the legacy tables have no idle encoding, so this is an
+     *  error.
+     */
+    pub fn from_solution(
+        solution: &Solution,
+        blocks: usize,
+    ) -> Result<Self, String> {
+        let context = &solution.context;
+        let trate = context.clock_multiplier;
+        let mut units = Vec::with_capacity(context.num_units);
+
+        for u in 0..context.num_units {
+            let mut pairs: Vec<Option<(usize, usize)>> = vec![None; trate];
+            for (t, slot) in solution.slots[u].iter().enumerate() {
+                if slot.means {
+                    warn!(
+                        "Unit {}, time-slot {}: signal-means not supported",
+                        u, t
+                    );
+                } else {
+                    pairs[t] = Some((slot.a, slot.b));
+                }
+            }
+
+            let fill =
+                pairs.iter().flatten().next().copied().ok_or(format!(
+                "Unit {}: no visibility calculations, to fill its time-slices",
+                u
+            ))?;
+            units.push(pairs.iter().map(|p| p.unwrap_or(fill)).collect());
+        }
+
+        Ok(Self {
+            num_antennas: context.num_antennas,
+            index_bits: clog2(context.num_antennas).max(1),
+            trate,
+            blocks: blocks.max(1),
+            units,
+        })
+    }
+
+    /**
+     *  Verilog `PAIRSxx_yy` parameter declarations, in the same layout as
+     *  `tart_24x_radio.v`.
+     */
+    pub fn to_verilog(&self) -> String {
+        let bits = 2 * self.index_bits * self.trate;
+        let mut out = Vec::with_capacity(self.num_units());
+
+        for (u, pairs) in self.units.iter().enumerate() {
+            let fields: Vec<usize> =
+                pairs.iter().flat_map(|&(a, b)| [a, b]).collect();
+            out.push(format!(
+                "  parameter PAIRS{:02}_{:02} = {{{}'h{}}};",
+                u / self.blocks,
+                u % self.blocks,
+                bits,
+                pack_hex(&fields, self.index_bits)
+            ));
+        }
+
+        out.join("\n") + "\n"
+    }
+
+    pub fn num_units(&self) -> usize {
+        self.units.len()
+    }
+
+    /**
+     *  The (sorted) A- & B- MUX inputs implied by the pairs of the unit.
+     */
+    pub fn mux_inputs(&self, unit: usize) -> (Vec<usize>, Vec<usize>) {
+        let mut a_mux: Vec<usize> =
+            self.units[unit].iter().map(|p| p.0).collect();
+        let mut b_mux: Vec<usize> =
+            self.units[unit].iter().map(|p| p.1).collect();
+        a_mux.sort();
+        a_mux.dedup();
+        b_mux.sort();
+        b_mux.dedup();
+        (a_mux, b_mux)
+    }
+
+    /**
+     *  Width of the widest A-/B- MUX, over all units.
+     */
+    pub fn mux_width(&self) -> usize {
+        (0..self.num_units())
+            .map(|u| {
+                let (a_mux, b_mux) = self.mux_inputs(u);
+                a_mux.len().max(b_mux.len())
+            })
+            .max()
+            .unwrap_or_default()
+    }
+
+    /**
+     *  Time-slices that repeat a calculation already performed by an earlier
+     *  slice, as (unit, slice) pairs.
+     */
+    pub fn repeats(&self) -> Vec<(usize, usize)> {
+        let mut seen = vec![false; self.num_antennas * self.num_antennas];
+        let mut repeats = Vec::new();
+
+        for (u, pairs) in self.units.iter().enumerate() {
+            for (t, &(a, b)) in pairs.iter().enumerate() {
+                let e = a.min(b) * self.num_antennas + a.max(b);
+                if seen[e] {
+                    repeats.push((u, t));
+                }
+                seen[e] = true;
+            }
+        }
+        repeats
+    }
+
+    /**
+     *  Swap the A- & B- antennas of some of the pairs, so that each unit uses
+     *  the narrowest MUXs (then the fewest MUX inputs). Swapped pairs compute
+     *  the conjugate visibilities.
+     */
+    pub fn reoriented(&self) -> Self {
+        let mut result = self.clone();
+
+        for pairs in result.units.iter_mut() {
+            let mut distinct: Vec<(usize, usize)> = Vec::new();
+            for &(a, b) in pairs.iter() {
+                if !distinct.contains(&(a, b)) && !distinct.contains(&(b, a)) {
+                    distinct.push((a, b));
+                }
+            }
+            if distinct.len() >= usize::BITS as usize {
+                warn!("Too many distinct pairs to reorient");
+                continue;
+            }
+
+            // Exhaustive search, as there are at most 'trate' pairs.
+            let mut best = (usize::MAX, usize::MAX, 0);
+            for mask in 0..1usize << distinct.len() {
+                let mut a_mux: Vec<usize> = Vec::new();
+                let mut b_mux: Vec<usize> = Vec::new();
+                for (i, &(a, b)) in distinct.iter().enumerate() {
+                    let (a, b) =
+                        if (mask >> i) & 1 == 1 { (b, a) } else { (a, b) };
+                    if !a_mux.contains(&a) {
+                        a_mux.push(a);
+                    }
+                    if !b_mux.contains(&b) {
+                        b_mux.push(b);
+                    }
+                }
+                let cost = (
+                    a_mux.len().max(b_mux.len()),
+                    a_mux.len() + b_mux.len(),
+                    mask,
+                );
+                best = best.min(cost);
+            }
+
+            let mask = best.2;
+            for p in pairs.iter_mut() {
+                let i = distinct
+                    .iter()
+                    .position(|&(a, b)| *p == (a, b) || *p == (b, a))
+                    .unwrap();
+                let (a, b) = distinct[i];
+                *p = if (mask >> i) & 1 == 1 { (b, a) } else { (a, b) };
+            }
+        }
+
+        result
+    }
+
+    /**
+     *  Build the context for the legacy assignment, with the MUX inputs, and
+     *  MUX-width, implied by the pairs.
+     */
+    pub fn to_context(&self) -> Context {
+        let units = self.num_units();
+        let mux_width = self.mux_width().max(1);
+        let mut context = Context::new(self.num_antennas, self.trate, true, 0);
+
+        context.num_units = units;
+        context.mux_width = mux_width;
+        context.a_mux_array = Chunked::new(mux_width, units);
+        context.b_mux_array = Chunked::new(mux_width, units);
+
+        for u in 0..units {
+            let (a_mux, b_mux) = self.mux_inputs(u);
+            for &a in a_mux.iter() {
+                context.a_mux_array.push(u, a);
+                context.nodes_count[a] += 1;
+            }
+            for &b in b_mux.iter() {
+                context.b_mux_array.push(u, b);
+                context.nodes_count[b] += 1;
+            }
+            for &(a, b) in self.units[u].iter() {
+                let e = context.calc_edge_index(a, b);
+                context.edges_count[e] += 1;
+            }
+        }
+
+        context
+    }
+
+    /**
+     *  Convert to a solution, keeping the legacy order of the time-slices, but
+     *  with any repeated calculations (of a unit) removed.
+     */
+    pub fn to_solution(&self) -> Solution {
+        let context = self.to_context();
+        let units = self.num_units();
+        let mut edges = Chunked::new(self.trate, units);
+        let mut slots = Chunked::new(self.trate, units);
+
+        for (u, pairs) in self.units.iter().enumerate() {
+            let a_mux = &context.a_mux_array[u];
+            let b_mux = &context.b_mux_array[u];
+
+            for &(a, b) in pairs.iter() {
+                edges.push(u, context.calc_edge_index(a, b));
+                slots.push(
+                    u,
+                    Slot {
+                        a_sel: a_mux.iter().position(|&x| x == a).unwrap(),
+                        b_sel: b_mux.iter().position(|&x| x == b).unwrap(),
+                        a,
+                        b,
+                        means: false,
+                    },
+                );
+            }
+        }
+
+        Solution {
+            context,
+            edges,
+            means: Chunked::new(1, units),
+            slots,
+        }
+    }
+}
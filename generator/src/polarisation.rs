@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::context::*;
+
+/**
+ * Polarisation of a feed, of a dual-polarisation antenna.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Polarisation {
+    X,
+    Y,
+}
+
+impl fmt::Display for Polarisation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::X => write!(f, "X"),
+            Self::Y => write!(f, "Y"),
+        }
+    }
+}
+
+/**
+ * Label of a correlator output, which is either a visibility, or a pair of
+ * signal-means, with the antennas of its A- & B- inputs, and the polarisation
+ * of each (for dual-polarisation). A signal-means slot with just one antenna
+ * is padded with an input of the other MUX, so that its mean is computed
+ * again, and this is marked by 'repeated' (for the A- & B- inputs).
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Product {
+    pub a: usize,
+    pub b: usize,
+    pub pols: Option<(Polarisation, Polarisation)>,
+    pub means: bool,
+    #[serde(default)]
+    pub repeated: (bool, bool),
+}
+
+impl Product {
+    /**
+     *  Polarisation pair of the product, as 'XX', 'YY', 'XY', or 'YX'.
+     */
+    pub fn pol_pair(&self) -> Option<String> {
+        self.pols.map(|(p, q)| format!("{}{}", p, q))
+    }
+}
+
+impl fmt::Display for Product {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (p, q) = match self.pols {
+            Some((p, q)) => (p.to_string(), q.to_string()),
+            None => (String::new(), String::new()),
+        };
+        if self.means {
+            let (a, b) =
+                (format!("{}{}", self.a, p), format!("{}{}", self.b, q));
+            let wrap =
+                |x: String, r: bool| if r { format!("({})", x) } else { x };
+            let (ra, rb) = self.repeated;
+            write!(f, "means: {}, {}", wrap(a, ra), wrap(b, rb))
+        } else {
+            write!(f, "{}{} -> {}{}", self.a, p, self.b, q)
+        }
+    }
+}
+
+impl Context {
+    /**
+     *  Dual-polarisation context, where the nodes are the X- & Y- feeds of the
+     *  antennas, so node '2a + p' is polarisation 'p' of antenna 'a', and
+     *  'num_antennas' is then the number of feeds. Each antenna-pair '(a, b)'
+     *  has the XX, YY, XY, and YX products, and each antenna has the XY
+     *  product of its own feeds, so these are all of the feed-pairs. Signal-
+     *  means are computed for each feed.
+     *
+     *  For a subset of the antenna-pairs, each pair '(a, b)' gives its four
+     *  products, and a pair '(a, a)' gives the XY product of antenna 'a'.
+     */
+    pub fn dual_pol(
+        num_antennas: usize,
+        edges: Option<&[(usize, usize)]>,
+        clock_multiplier: usize,
+        no_means: bool,
+        extra_bits: usize,
+    ) -> Result<Self, String> {
+        let num_feeds = 2 * num_antennas;
+        let mut context = match edges {
+            None => {
+                Self::new(num_feeds, clock_multiplier, no_means, extra_bits)
+            }
+            Some(edges) => {
+                let mut feeds = Vec::with_capacity(4 * edges.len());
+                for &(a, b) in edges.iter() {
+                    if a.max(b) >= num_antennas {
+                        return Err(format!(
+                            "Edge {} -> {} is not between the {} antennas",
+                            a, b, num_antennas
+                        ));
+                    }
+                    if a == b {
+                        feeds.push((2 * a, 2 * a + 1));
+                        continue;
+                    }
+                    for p in 0..2 {
+                        for q in 0..2 {
+                            feeds.push((2 * a + p, 2 * b + q));
+                        }
+                    }
+                }
+                Self::with_edges(
+                    num_feeds,
+                    &feeds,
+                    clock_multiplier,
+                    no_means,
+                    extra_bits,
+                )?
+            }
+        };
+        context.dual_pol = true;
+        Ok(context)
+    }
+
+    /**
+     *  Number of (physical) antennas, which each have two feeds, when using
+     *  dual-polarisation.
+     */
+    pub fn num_dishes(&self) -> usize {
+        if self.dual_pol {
+            self.num_antennas >> 1
+        } else {
+            self.num_antennas
+        }
+    }
+
+    /**
+     *  Antenna, and polarisation (for dual-polarisation), of the node.
+     */
+    pub fn feed(&self, node: usize) -> (usize, Option<Polarisation>) {
+        match (self.dual_pol, node & 1) {
+            (false, _) => (node, None),
+            (true, 0) => (node >> 1, Some(Polarisation::X)),
+            (true, _) => (node >> 1, Some(Polarisation::Y)),
+        }
+    }
+
+    /**
+     *  Nodes (i.e., the feeds, for dual-polarisation) of the antenna.
+     */
+    pub fn antenna_nodes(&self, antenna: usize) -> Vec<usize> {
+        if self.dual_pol {
+            vec![2 * antenna, 2 * antenna + 1]
+        } else {
+            vec![antenna]
+        }
+    }
+
+    /**
+     *  Label of the node, which is the antenna, and its polarisation.
+     */
+    pub fn node_label(&self, node: usize) -> String {
+        match self.feed(node) {
+            (a, Some(p)) => format!("{}{}", a, p),
+            (a, None) => a.to_string(),
+        }
+    }
+
+    /**
+     *  Label of the product (or signal-means) of the A- & B- nodes.
+     */
+    pub fn product(
+        &self,
+        node_a: usize,
+        node_b: usize,
+        means: bool,
+    ) -> Product {
+        let ((a, p), (b, q)) = (self.feed(node_a), self.feed(node_b));
+        Product {
+            a,
+            b,
+            pols: p.zip(q),
+            means,
+            repeated: (false, false),
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::context::*;
+
+/**
+ * Replaces the 'curr' node, of the A- (or B-) MUX of the correlator 'unit',
+ * with the 'next' node.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Replacement {
+    pub unit: usize,
+    pub b_mux: bool,
+    pub curr: usize,
+    pub next: usize,
+}
+
+impl fmt::Display for Replacement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mux = if self.b_mux { 'B' } else { 'A' };
+        write!(
+            f,
+            "COR{} {}-MUX: {} -> {}",
+            self.unit, mux, self.curr, self.next
+        )
+    }
+}
+
+impl Context {
+    /**
+     *  Required edges that are not computed by any correlator unit.
+     */
+    pub fn uncovered_edges(&self) -> Vec<usize> {
+        (0..self.num_edges)
+            .filter(|&e| self.edges_count[e] == 0 && self.is_required(e))
+            .collect()
+    }
+
+    /**
+     *  A node is removable from a MUX if each of its edges, with the nodes of
+     *  the other MUX (of that unit), is also computed by another unit, where
+     *  products that are not edges (of a subset) are never needed.
+     */
+    pub fn is_removable(&self, unit: usize, b_mux: bool, node: usize) -> bool {
+        let other = if b_mux {
+            &self.a_mux_array[unit]
+        } else {
+            &self.b_mux_array[unit]
+        };
+        other.iter().all(|&x| self.edge_count(node, x) != Some(1))
+    }
+
+    /**
+     *  Finds the cheapest replacement that covers the (uncovered) edge, where
+     *  one of its nodes is already in a MUX of the unit, and a removable node
+     *  of the other MUX is replaced by the other node of the edge.
+     */
+    pub fn find_replacement(&self, edge: usize) -> Option<Replacement> {
+        let (a, b) = self.edges_array[edge];
+        let mut best: Option<(usize, Replacement)> = None;
+
+        for unit in 0..self.num_units {
+            let (a_mux, b_mux) =
+                (&self.a_mux_array[unit], &self.b_mux_array[unit]);
+            for (x, y) in [(a, b), (b, a)] {
+                if a_mux.contains(&x) || b_mux.contains(&x) {
+                    continue;
+                }
+                // Replace a node of the MUX that is opposite to 'y'
+                let (mux, b_side) = if b_mux.contains(&y) {
+                    (a_mux, false)
+                } else if a_mux.contains(&y) {
+                    (b_mux, true)
+                } else {
+                    continue;
+                };
+                for &curr in mux.iter() {
+                    if !self.is_removable(unit, b_side, curr) {
+                        continue;
+                    }
+                    let score = self.replace_score(unit, curr, x);
+                    if best.is_none_or(|(s, _)| score < s) {
+                        let r = Replacement {
+                            unit,
+                            b_mux: b_side,
+                            curr,
+                            next: x,
+                        };
+                        best = Some((score, r));
+                    }
+                }
+            }
+        }
+        best.map(|(_, r)| r)
+    }
+
+    /**
+     *  Refines the partition, by replacing removable MUX inputs, until all
+     *  edges are covered, or no more edges can be covered this way. Each
+     *  replacement covers at least one more edge, and uncovers none, so this
+     *  terminates. Returns the replacements, in the order that they were made.
+     */
+    pub fn refine(&mut self) -> Vec<Replacement> {
+        let mut moves = Vec::new();
+        loop {
+            let mut progress = false;
+            for edge in self.uncovered_edges() {
+                if self.edges_count[edge] > 0 {
+                    continue;
+                }
+                if let Some(r) = self.find_replacement(edge) {
+                    debug!("Refinement: {}", r);
+                    self.replace(r.unit, r.curr, r.next);
+                    moves.push(r);
+                    progress = true;
+                }
+            }
+            if !progress || self.is_complete() {
+                break;
+            }
+        }
+        self.sort_inputs();
+        moves
+    }
+}
@@ -0,0 +1,159 @@
+use std::fmt;
+
+use crate::estimate::clog2;
+use crate::solution::Solution;
+
+/**
+ * Parameters of a `sigsource` instance, for a single correlator unit: the
+ * antenna signals that are tapped-off for each of the A- & B- MUX inputs, the
+ * (per time-slot) MUX-selects, and the time-slots that compute signal-means
+ * ('AUTOS').
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SigSource {
+    pub width: usize,
+    pub mux_n: usize,
+    pub trate: usize,
+    pub ataps: Vec<usize>,
+    pub btaps: Vec<usize>,
+    pub asels: Vec<usize>,
+    pub bsels: Vec<usize>,
+    pub autos: Vec<usize>,
+}
+
+/**
+ *  Pack the 'width'-bit fields into a hex-string, with the first value in the
+ *  least-significant bits, as for the Verilog `X[ii*W+W-1:ii*W]` parameters.
+ */
+pub fn pack_hex(values: &[usize], width: usize) -> String {
+    let bits = values.len() * width;
+    let mut nibbles = vec![0u8; bits.div_ceil(4).max(1)];
+
+    for (i, &value) in values.iter().enumerate() {
+        for j in 0..width {
+            if (value >> j) & 1 == 1 {
+                let k = i * width + j;
+                nibbles[k >> 2] |= 1 << (k & 3);
+            }
+        }
+    }
+
+    nibbles
+        .iter()
+        .rev()
+        .map(|&x| char::from_digit(x as u32, 16).unwrap())
+        .collect()
+}
+
+impl SigSource {
+    /**
+     *  Extract the taps and selects of the unit, from the solution. Unused
+     *  taps, and the selects for idle time-slots, are set to zero.
+     */
+    pub fn new(solution: &Solution, unit: usize) -> Self {
+        let context = &solution.context;
+        let mux_n = context.mux_width;
+        let trate = context.clock_multiplier;
+
+        let mut ataps: Vec<usize> = context.a_mux_array[unit].to_vec();
+        let mut btaps: Vec<usize> = context.b_mux_array[unit].to_vec();
+        ataps.resize(mux_n, 0);
+        btaps.resize(mux_n, 0);
+
+        let mut asels = vec![0; trate];
+        let mut bsels = vec![0; trate];
+        let mut autos = vec![0; trate];
+        for (t, slot) in solution.slots[unit].iter().enumerate() {
+            asels[t] = slot.a_sel;
+            bsels[t] = slot.b_sel;
+            autos[t] = slot.means as usize;
+        }
+
+        Self {
+            width: context.num_antennas,
+            mux_n,
+            trate,
+            ataps,
+            btaps,
+            asels,
+            bsels,
+            autos,
+        }
+    }
+
+    /**
+     *  Bit-widths of each antenna tap ('SBITS'), and of each MUX-select
+     *  ('XBITS'), as computed by `sigsource`, but at least one bit.
+     */
+    pub fn sbits(&self) -> usize {
+        clog2(self.width).max(1)
+    }
+
+    pub fn xbits(&self) -> usize {
+        clog2(self.mux_n).max(1)
+    }
+
+    pub fn pbits(&self) -> usize {
+        self.sbits() * self.mux_n
+    }
+
+    pub fn qbits(&self) -> usize {
+        self.xbits() * self.trate
+    }
+
+    /**
+     *  Verilog parameter assignments, for instantiating a `sigsource`.
+     */
+    pub fn to_verilog(&self, suffix: &str) -> String {
+        let (p, q) = (self.pbits(), self.qbits());
+        let mut out = Vec::with_capacity(5);
+        for (name, bits, values, width) in [
+            ("ATAPS", p, &self.ataps, self.sbits()),
+            ("BTAPS", p, &self.btaps, self.sbits()),
+            ("ASELS", q, &self.asels, self.xbits()),
+            ("BSELS", q, &self.bsels, self.xbits()),
+            ("AUTOS", self.trate, &self.autos, 1),
+        ] {
+            out.push(format!(
+                "localparam unsigned [{}:0] {}{} = {}'h{};",
+                bits - 1,
+                name,
+                suffix,
+                bits,
+                pack_hex(values, width)
+            ));
+        }
+        out.join("\n")
+    }
+}
+
+impl fmt::Display for SigSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.to_verilog(""))
+    }
+}
+
+/**
+ *  The `sigsource` parameters for every unit of the solution, with the unit-
+ *  index appended to each parameter name.
+ */
+pub fn sigsource_params(solution: &Solution) -> String {
+    let context = &solution.context;
+    let mut out = vec![
+        format!("localparam integer WIDTH = {};", context.num_antennas),
+        format!("localparam integer MUX_N = {};", context.mux_width),
+        format!("localparam integer TRATE = {};", context.clock_multiplier),
+    ];
+
+    for u in 0..context.num_units {
+        let source = SigSource::new(solution, u);
+        out.push(String::new());
+        out.push(format!(
+            "// Unit {}: A-MUX {:?}, B-MUX {:?}",
+            u, &context.a_mux_array[u], &context.b_mux_array[u]
+        ));
+        out.push(source.to_verilog(&format!("{:02}", u)));
+    }
+
+    out.join("\n") + "\n"
+}
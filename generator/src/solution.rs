@@ -0,0 +1,312 @@
+use log::{debug, error};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::chunked::Chunked;
+use crate::context::Context;
+use crate::polarisation::Product;
+
+/**
+ * A time-slot of a correlator unit, with the A- & B- MUX selects, the antennas
+ * that these select, and whether the slot computes signal-means (instead of a
+ * visibility).
+ */
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Slot {
+    pub a_sel: usize,
+    pub b_sel: usize,
+    pub a: usize,
+    pub b: usize,
+    pub means: bool,
+}
+
+impl fmt::Debug for Slot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.means {
+            write!(f, "({}*, {}*)", self.a_sel, self.b_sel)
+        } else {
+            write!(f, "({}, {})", self.a_sel, self.b_sel)
+        }
+    }
+}
+
+/**
+ * A complete correlator configuration: the MUX assignments (of the context),
+ * the visibility and signal-means calculations assigned to each unit, and the
+ * resulting MUX-selects for each time-slot.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Solution {
+    pub context: Context,
+    pub edges: Chunked<usize>,
+    pub means: Chunked<(usize, usize)>,
+    pub slots: Chunked<Slot>,
+}
+
+impl Context {
+    /**
+     *  Computes the (A, B) MUX-select indices, for the visibility calculations
+     *  that have been assigned to each correlator unit.
+     */
+    pub fn mux_selects(
+        &self,
+        edges: &Chunked<usize>,
+    ) -> Chunked<(usize, usize)> {
+        let edge_num = self.edges_array.len();
+
+        // Create a LUT: Edge -> Core.
+        let mut edge_to_core: Vec<usize> = vec![usize::MAX; edge_num];
+
+        for (u, core) in edges.into_iter().enumerate() {
+            debug!("core[{}]: {:?}", u, core);
+            for &e in core.iter() {
+                edge_to_core[e] = u;
+            }
+        }
+
+        let mut selects = Chunked::new(self.clock_multiplier, self.num_units);
+
+        for (u, (a_mux, b_mux)) in self
+            .a_mux_array
+            .into_iter()
+            .zip(&self.b_mux_array)
+            .enumerate()
+        {
+            let mut i: usize = 0;
+            let mut j: usize = 0;
+
+            while i < a_mux.len() && j < b_mux.len() {
+                let a = a_mux[i];
+                let b = b_mux[j];
+
+                if a < b {
+                    for (k, &b) in b_mux.iter().enumerate().skip(j) {
+                        let e = self.find_edge(a, b);
+                        if e.is_some_and(|e| edge_to_core[e] == u) {
+                            selects.push(u, (i, k));
+                        }
+                    }
+                    if i < a_mux.len() {
+                        i += 1;
+                    }
+                } else {
+                    for (k, &a) in a_mux.iter().enumerate().skip(i) {
+                        let e = self.find_edge(a, b);
+                        if e.is_some_and(|e| edge_to_core[e] == u) {
+                            selects.push(u, (k, j));
+                        }
+                    }
+                    if j < b_mux.len() {
+                        j += 1;
+                    }
+                }
+            }
+        }
+
+        selects
+    }
+}
+
+impl Solution {
+    /**
+     *  Builds the time-slots for each unit, with the visibility calculations
+     *  first, followed by the signal-means calculations.
+     */
+    pub fn new(
+        context: Context,
+        edges: Chunked<usize>,
+        means: Chunked<(usize, usize)>,
+    ) -> Self {
+        let selects = context.mux_selects(&edges);
+        let mut slots =
+            Chunked::new(context.clock_multiplier, context.num_units);
+
+        for u in 0..context.num_units {
+            let a_mux = &context.a_mux_array[u];
+            let b_mux = &context.b_mux_array[u];
+
+            for &(i, j) in selects[u].iter() {
+                slots.push(
+                    u,
+                    Slot {
+                        a_sel: i,
+                        b_sel: j,
+                        a: a_mux[i],
+                        b: b_mux[j],
+                        means: false,
+                    },
+                );
+            }
+
+            if u >= means.len() {
+                continue;
+            }
+
+            // Signal-means pairs may have been placed as either (A, B), or
+            // (B, A).
+            for &(x, y) in means[u].iter() {
+                let (a, b) = if a_mux.contains(&x) && b_mux.contains(&y) {
+                    (x, y)
+                } else {
+                    (y, x)
+                };
+                match (
+                    a_mux.iter().position(|&n| n == a),
+                    b_mux.iter().position(|&n| n == b),
+                ) {
+                    (Some(i), Some(j)) => slots.push(
+                        u,
+                        Slot {
+                            a_sel: i,
+                            b_sel: j,
+                            a,
+                            b,
+                            means: true,
+                        },
+                    ),
+                    _ => error!("Invalid signal-means pair: ({}, {})", x, y),
+                }
+            }
+        }
+
+        Self {
+            context,
+            edges,
+            means,
+            slots,
+        }
+    }
+
+    /**
+     *  Assign the visibility calculations, along with the signal-means, to
+     *  each correlator unit, of the (partitioned) context.
+     */
+    pub fn solve(context: &mut Context) -> Option<Self> {
+        let routing = context.assign_edges(true)?;
+        Some(Self::new(context.clone(), routing.edges, routing.means))
+    }
+
+    /**
+     *  Number of (time-multiplexed) outputs, including idle slots, for each
+     *  set of partial-sums.
+     */
+    pub fn num_outputs(&self) -> usize {
+        self.context.num_units * self.context.clock_multiplier
+    }
+
+    /**
+     *  The calculation for each output, in unit-major, then time-slot order,
+     *  with 'None' for idle time-slots.
+     */
+    pub fn output_order(&self) -> Vec<Option<Slot>> {
+        let trate = self.context.clock_multiplier;
+        let mut order = Vec::with_capacity(self.num_outputs());
+
+        for u in 0..self.context.num_units {
+            for t in 0..trate {
+                order.push(self.slots[u].get(t).copied());
+            }
+        }
+
+        order
+    }
+
+    /**
+     *  Label of each output, in the output-order, with the antennas, and the
+     *  polarisation pair (for dual-polarisation), of each product. Signal-
+     *  means that have already been computed, by an earlier output, are
+     *  marked as repeated.
+     */
+    pub fn output_labels(&self) -> Vec<Option<Product>> {
+        let mut seen = vec![false; self.context.num_antennas];
+        let mut repeat = |x: usize| std::mem::replace(&mut seen[x], true);
+        self.output_order()
+            .iter()
+            .map(|slot| {
+                slot.map(|s| {
+                    let mut p = self.context.product(s.a, s.b, s.means);
+                    if s.means {
+                        p.repeated = (repeat(s.a), repeat(s.b));
+                    }
+                    p
+                })
+            })
+            .collect()
+    }
+
+    /**
+     *  Required edges that are not computed by any time-slot.
+     */
+    pub fn missing_edges(&self) -> Vec<usize> {
+        let mut found = vec![false; self.context.num_edges];
+        for slot in self.slots.into_iter().flatten() {
+            if !slot.means {
+                found[self.context.calc_edge_index(slot.a, slot.b)] = true;
+            }
+        }
+        (0..self.context.num_edges)
+            .filter(|&e| !found[e] && self.context.is_required(e))
+            .collect()
+    }
+
+    /**
+     *  Active antennas (see 'is_active') without a signal-means calculation.
+     */
+    pub fn missing_means(&self) -> Vec<usize> {
+        if self.context.no_means {
+            return Vec::new();
+        }
+        let mut found = vec![false; self.context.num_antennas];
+        for slot in self.slots.into_iter().flatten() {
+            if slot.means {
+                found[slot.a] = true;
+                found[slot.b] = true;
+            }
+        }
+        (0..self.context.num_antennas)
+            .filter(|&n| !found[n] && self.context.is_active(n))
+            .collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.missing_edges().is_empty() && self.missing_means().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auto::AutoSolve;
+
+    #[test]
+    fn solves_24_antennas() {
+        let mut context = Context::new(24, 12, false, 1);
+        context.partition(false);
+        let solution = Solution::solve(&mut context).expect("No solution");
+        assert!(solution.is_complete());
+    }
+    #[test]
+    fn solves_sparse_subset() {
+        let edges = [(0, 1), (0, 2), (1, 3), (5, 7), (2, 6)];
+        let mut context =
+            Context::with_edges(8, &edges, 12, false, 0).expect("Bad edges");
+        let inputs =
+            context.num_units * (context.a_width() + context.b_width());
+        assert!(inputs >= context.num_active());
+        context.partition(false);
+        context.refine();
+        let solution = Solution::solve(&mut context).expect("No solution");
+        assert!(solution.is_complete());
+    }
+    #[test]
+    fn solves_with_dead_antenna() {
+        let context = Context::new(8, 12, false, 0)
+            .with_masks(&[3], &[])
+            .expect("Bad masks");
+        assert_eq!(context.a_width() + context.b_width(), 6);
+        let solve = AutoSolve::new(context, 0);
+        let solution = solve.solution.expect("No solution");
+        assert!(solution.is_complete());
+        assert!(solution.context.is_dead(3));
+    }
+}
@@ -0,0 +1,418 @@
+use crate::sigsource::SigSource;
+use crate::solution::Solution;
+use crate::widths::BitWidths;
+
+/**
+ * Generates a complete correlator top-level module, in the same style as
+ * `correlator_24x_radio` & `toy_correlator`, for a solved configuration:
+ *  - a `sigbuffer`, for the antenna signals;
+ *  - a `correlator` per unit, each with its own taps and selects, and with
+ *    daisy-chained outputs (as in `tart_correlator`);
+ *  - a `visaccum` and `accumulator`, for the partial-sums and visibilities;
+ *    and
+ *  - an `axis_afifo` and `axis_adapter`, for the 8-bit AXI4-Stream output.
+ */
+#[derive(Debug, Clone)]
+pub struct TopLevel<'a> {
+    pub name: String,
+    pub solution: &'a Solution,
+    pub widths: &'a BitWidths,
+}
+
+impl<'a> TopLevel<'a> {
+    pub fn new(
+        name: &str,
+        solution: &'a Solution,
+        widths: &'a BitWidths,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            solution,
+            widths,
+        }
+    }
+
+    /**
+     *  Default module name, e.g., `correlator_24x_radio`.
+     */
+    pub fn default_name(solution: &Solution) -> String {
+        format!("correlator_{}x_radio", solution.context.num_antennas)
+    }
+
+    fn header(&self) -> String {
+        let context = &self.solution.context;
+        let w = self.widths;
+        format!(
+            r#"`timescale 1ns / 100ps
+/**
+ * Generated by 'tart-dsp', for {antennas} antennas, using {cores} correlators at
+ * {trate}x the sample-rate, and with the correlators MUX-width of {mux_n}.
+ */
+module {name} #(
+    parameter AFIFO_DEPTH = 16,
+    localparam FBITS = $clog2(AFIFO_DEPTH),
+
+    localparam AXIS_DWIDTH = 8,
+    localparam AXIS_DKEEPS = AXIS_DWIDTH / 8,
+    localparam DSB = AXIS_DWIDTH - 1,
+    localparam KSB = AXIS_DKEEPS - 1,
+
+    localparam integer WIDTH = {antennas},  // Number of antennas/signals
+    localparam integer MSB = WIDTH - 1,
+
+    // Source-signal multiplexor parameters
+    localparam integer MUX_N = {mux_n},
+
+    localparam integer CORES = {cores},  // Number of correlator cores
+
+    // Time-multiplexing rate, i.e., clock multiplier
+    localparam integer TRATE = {trate},
+    localparam integer TBITS = $clog2(TRATE),
+    localparam integer TSB   = TBITS - 1,
+
+    // Every 'COUNT' samples, compute partial-visibilities to accumumlate
+    localparam integer LOOP0 = {loop0},
+    localparam integer LOOP1 = {loop1},
+
+    localparam integer ACCUM = {accum},  // Bit-width of accumulators
+    localparam integer VSB = ACCUM - 1,
+    // Output stream word, of both components, padded to whole bytes
+    localparam integer WBITS = 8 * ((ACCUM + ACCUM + 7) / 8),
+    localparam integer WSB = WBITS - 1,
+
+    localparam integer ABITS = {abits},  // Bit-width of adders
+    localparam integer ASB = ABITS - 1,
+
+    localparam integer SBITS = {sbits},  // Bit-width of partial-sums
+    localparam integer SSB = SBITS - 1,
+
+    // Number of partial-sums for each visibility
+    localparam integer PASSES = {passes},
+    localparam integer LSB = ACCUM - SBITS
+) (
+    input sig_clock,  // Sample-clock (default: 16.368 MHz)
+    input areset_n,
+
+    input bus_clock,  // SPI/USB clock for reading visibilities
+    input bus_reset,
+
+    input vis_clock,  // Correlator clock ({trate}x the sample-clock)
+    input vis_reset,
+
+    // Status signals
+    output vis_start_o,
+    output vis_frame_o,
+
+    // AXI4 Stream of antenna data
+    input sig_valid_i,
+    input sig_last_i,
+    input [MSB:0] sig_idata_i,
+    input [MSB:0] sig_qdata_i,
+
+    // AXI4 Stream of visibilities data
+    output m_tvalid,
+    input m_tready,
+    output [KSB:0] m_tkeep,
+    output m_tlast,
+    output [DSB:0] m_tdata
+);
+"#,
+            name = self.name,
+            antennas = context.num_antennas,
+            mux_n = context.mux_width,
+            cores = context.num_units,
+            trate = context.clock_multiplier,
+            loop0 = w.loop0,
+            loop1 = w.loop1,
+            accum = w.accum,
+            abits = w.abits,
+            sbits = w.sbits,
+            passes = w.passes,
+        )
+    }
+
+    fn sigbuffer(&self) -> String {
+        r#"
+  /**
+   * Input-buffering SRAM's for (antenna) signal IQ data.
+   */
+  wire buf_valid_w, buf_first_w, buf_next_w, buf_emit_w, buf_last_w;
+  wire [TSB:0] buf_taddr_w;
+  wire [MSB:0] buf_idata_w, buf_qdata_w;
+
+  sigbuffer #(
+      .WIDTH(WIDTH),
+      .TRATE(TRATE),
+      .LOOP0(LOOP0),
+      .LOOP1(LOOP1)
+  ) SIGBUF0 (
+      .sig_clk(sig_clock),
+      .reset_n(areset_n),
+      .valid_i(sig_valid_i),
+      .idata_i(sig_idata_i),
+      .qdata_i(sig_qdata_i),
+
+      .vis_clk(vis_clock),
+      .vis_rst(vis_reset),
+      .valid_o(buf_valid_w),
+      .first_o(buf_first_w),
+      .next_o (buf_next_w),
+      .emit_o (buf_emit_w),
+      .last_o (buf_last_w),
+      .taddr_o(buf_taddr_w),
+      .idata_o(buf_idata_w),
+      .qdata_o(buf_qdata_w)
+  );
+
+
+  // -- Correlator status signals -- //
+
+  reg start, frame;
+
+  assign vis_start_o = start;
+  assign vis_frame_o = frame;
+
+  always @(posedge vis_clock) begin
+    if (vis_reset) begin
+      start <= 1'b0;
+      frame <= 1'b0;
+    end else begin
+      if (!frame && buf_valid_w && buf_first_w) begin
+        start <= 1'b1;
+        frame <= 1'b1;
+      end else begin
+        start <= 1'b0;
+      end
+    end
+  end
+
+"#
+        .to_string()
+    }
+
+    fn correlators(&self) -> String {
+        let context = &self.solution.context;
+        let mut out = vec![
+            "  /**".to_string(),
+            "   *  Correlator array, with daisy-chained outputs.".to_string(),
+            "   */".to_string(),
+            "  wire [ASB:0] re_w[CORES+1];".to_string(),
+            "  wire [ASB:0] im_w[CORES+1];".to_string(),
+            "  wire [CORES:0] vlds;".to_string(),
+            "  wire [CORES-1:0] frms;".to_string(),
+            String::new(),
+            "  assign vlds[0] = 1'b0;".to_string(),
+            "  assign re_w[0] = {ABITS{1'bx}};".to_string(),
+            "  assign im_w[0] = {ABITS{1'bx}};".to_string(),
+        ];
+
+        for u in 0..context.num_units {
+            let source = SigSource::new(self.solution, u);
+            let suffix = format!("{:02}", u);
+            let params = source
+                .to_verilog(&suffix)
+                .lines()
+                .map(|l| format!("  {}", l))
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            out.push(format!(
+                r#"
+  // Unit {u}: A-MUX {a:?}, B-MUX {b:?}
+{params}
+
+  correlator #(
+      .WIDTH(WIDTH),
+      .ABITS(ABITS),
+      .MUX_N(MUX_N),
+      .TRATE(TRATE),
+      .ATAPS(ATAPS{s}),
+      .BTAPS(BTAPS{s}),
+      .ASELS(ASELS{s}),
+      .BSELS(BSELS{s}),
+      .AUTOS(AUTOS{s})
+  ) U_CORE{s} (
+      .clock(vis_clock),
+      .reset(vis_reset),
+
+      .valid_i(buf_valid_w),
+      .first_i(buf_first_w),
+      .next_i (buf_next_w),
+      .emit_i (buf_emit_w),
+      .last_i (buf_last_w),
+      .taddr_i(buf_taddr_w),
+      .idata_i(buf_idata_w),
+      .qdata_i(buf_qdata_w),
+
+      .prevs_i(vlds[{u}]),
+      .revis_i(re_w[{u}]),
+      .imvis_i(im_w[{u}]),
+
+      .frame_o(frms[{u}]),
+      .valid_o(vlds[{n}]),
+      .revis_o(re_w[{n}]),
+      .imvis_o(im_w[{n}])
+  );"#,
+                u = u,
+                n = u + 1,
+                s = suffix,
+                a = &context.a_mux_array[u],
+                b = &context.b_mux_array[u],
+                params = params,
+            ));
+        }
+
+        out.join("\n") + "\n\n"
+    }
+
+    fn accumulators(&self) -> String {
+        r#"
+  /**
+   *  Partial-sums of the chained correlator outputs, and then accumulate
+   *  these into the full-width visibilities.
+   */
+  wire vis_frame, vis_valid, vis_first, vis_last;
+  wire [SSB:0] vis_rdata, vis_idata;
+
+  visaccum #(
+      .IBITS(ABITS),
+      .OBITS(SBITS),
+      .PSUMS(LOOP0),
+      .COUNT(LOOP1)
+  ) U_VISACC1 (
+      .clock(vis_clock),
+      .reset(vis_reset),
+
+      .frame_i(frms[0]),
+      .valid_i(vlds[CORES]),
+      .rdata_i(re_w[CORES]),
+      .idata_i(im_w[CORES]),
+
+      .frame_o(vis_frame),
+      .valid_o(vis_valid),
+      .first_o(vis_first),
+      .last_o (vis_last),
+      .rdata_o(vis_rdata),
+      .idata_o(vis_idata)
+  );
+
+  wire [LSB:0] vis_limit = PASSES;
+
+  wire [ACCUM-1:0] acc_revis, acc_imvis;
+  wire acc_valid, acc_last;
+
+  accumulator #(
+      .CORES(CORES),
+      .TRATE(TRATE),
+      .WIDTH(ACCUM),
+      .SBITS(SBITS)
+  ) U_ACCUM1 (
+      .clock(vis_clock),
+      .reset(vis_reset),
+
+      .count_i(vis_limit),
+      .frame_i(vis_frame),
+
+      .valid_i(vis_valid),
+      .first_i(vis_first),
+      .last_i (vis_last),
+      .revis_i(vis_rdata),
+      .imvis_i(vis_idata),
+
+      .valid_o(acc_valid),
+      .last_o (acc_last),
+      .revis_o(acc_revis),
+      .imvis_o(acc_imvis)
+  );
+
+"#
+        .to_string()
+    }
+
+    fn output(&self) -> String {
+        r#"
+  /**
+   *  Output FIFO, to the bus clock-domain, and then to the 8-bit AXI4-Stream.
+   */
+  localparam KEEPS = WBITS / 8;
+
+  wire b_tvalid, b_tready, b_tlast, acc_ready;
+  wire [WSB:0] b_tdata;
+
+  // Zero-extended to 'WBITS', so that 'KEEPS' is a whole number of bytes
+  wire [WSB:0] acc_tdata = {acc_revis, acc_imvis};
+
+  axis_afifo #(
+      .WIDTH(WBITS),
+      .ABITS(FBITS)
+  ) U_AFIFO1 (
+      .aresetn(areset_n),
+
+      .s_aclk(vis_clock),
+      .s_tvalid(acc_valid),
+      .s_tready(acc_ready),
+      .s_tlast(acc_last),
+      .s_tdata(acc_tdata),
+
+      .m_aclk(bus_clock),
+      .m_tvalid(b_tvalid),
+      .m_tready(b_tready),
+      .m_tlast(b_tlast),
+      .m_tdata(b_tdata)
+  );
+
+  wire [KEEPS-1:0] b_tkeeps_w = {KEEPS{b_tvalid}};
+
+  axis_adapter #(
+      .S_DATA_WIDTH(WBITS),
+      .S_KEEP_ENABLE(1),
+      .S_KEEP_WIDTH(KEEPS),
+      .M_DATA_WIDTH(AXIS_DWIDTH),
+      .M_KEEP_ENABLE(1),
+      .M_KEEP_WIDTH(AXIS_DKEEPS),
+      .ID_ENABLE(0),
+      .ID_WIDTH(1),
+      .DEST_ENABLE(0),
+      .DEST_WIDTH(1),
+      .USER_ENABLE(0),
+      .USER_WIDTH(1)
+  ) U_ADAPT1 (
+      .clk(bus_clock),
+      .rst(bus_reset),
+
+      .s_axis_tvalid(b_tvalid),
+      .s_axis_tready(b_tready),
+      .s_axis_tkeep(b_tkeeps_w),
+      .s_axis_tlast(b_tlast),
+      .s_axis_tid(1'b0),
+      .s_axis_tdest(1'b0),
+      .s_axis_tuser(1'b0),
+      .s_axis_tdata(b_tdata),
+
+      .m_axis_tvalid(m_tvalid),
+      .m_axis_tready(m_tready),
+      .m_axis_tkeep(m_tkeep),
+      .m_axis_tlast(m_tlast),
+      .m_axis_tid(),
+      .m_axis_tdest(),
+      .m_axis_tuser(),
+      .m_axis_tdata(m_tdata)
+  );
+
+
+endmodule  /* "#
+            .to_string()
+    }
+
+    pub fn to_verilog(&self) -> String {
+        [
+            self.header(),
+            self.sigbuffer(),
+            self.correlators(),
+            self.accumulators(),
+            self.output(),
+            format!("{} */\n", self.name),
+        ]
+        .concat()
+    }
+}
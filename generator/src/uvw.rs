@@ -0,0 +1,271 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::polarisation::Polarisation;
+use crate::solution::Solution;
+
+pub const SPEED_OF_LIGHT: f64 = 299_792_458.0;
+
+/**
+ * The GPS L1 frequency, as observed by TART, in Hz.
+ */
+pub const L1_FREQUENCY: f64 = 1_575.42e6;
+
+/**
+ * Antenna positions, in metres, as (east, north, up) offsets from the array
+ * reference position, along with the latitude (in degrees) of the array.
+ *
+ * Files can be YAML or JSON, and either a map with 'antenna_positions' (and an
+ * optional 'latitude'), or just the list of positions.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AntennaArray {
+    #[serde(default)]
+    pub latitude: f64,
+    pub antenna_positions: Vec<[f64; 3]>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ArrayFile {
+    Array(AntennaArray),
+    Positions(Vec<[f64; 3]>),
+}
+
+impl AntennaArray {
+    /**
+     *  Parse the positions, from YAML or JSON (as JSON is also valid YAML).
+     */
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let file: ArrayFile =
+            serde_yaml::from_str(source).map_err(|e| e.to_string())?;
+        Ok(match file {
+            ArrayFile::Array(array) => array,
+            ArrayFile::Positions(antenna_positions) => Self {
+                latitude: 0.0,
+                antenna_positions,
+            },
+        })
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read '{}': {}", path, e))?;
+        Self::parse(&source)
+    }
+
+    pub fn num_antennas(&self) -> usize {
+        self.antenna_positions.len()
+    }
+
+    /**
+     *  Baseline vector (ENU, in metres) for the visibility 'V_ab = <x_a x_b*>',
+     *  which is 'r_a - r_b'.
+     */
+    pub fn baseline(&self, a: usize, b: usize) -> [f64; 3] {
+        let (ra, rb) = (self.antenna_positions[a], self.antenna_positions[b]);
+        [ra[0] - rb[0], ra[1] - rb[1], ra[2] - rb[2]]
+    }
+}
+
+/**
+ * Phase-centre of the visibilities, as an hour-angle and declination (in
+ * radians), for an array at the given latitude.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Pointing {
+    pub latitude: f64,
+    pub hour_angle: f64,
+    pub declination: f64,
+}
+
+impl Pointing {
+    /**
+     *  Zenith pointing, for which (u, v, w) are (east, north, up).
+     */
+    pub fn zenith(latitude_deg: f64) -> Self {
+        let latitude = latitude_deg.to_radians();
+        Self {
+            latitude,
+            hour_angle: 0.0,
+            declination: latitude,
+        }
+    }
+
+    /**
+     *  Pointing from the azimuth (east of north) and elevation, in degrees.
+     */
+    pub fn from_horizontal(
+        azimuth_deg: f64,
+        elevation_deg: f64,
+        latitude_deg: f64,
+    ) -> Self {
+        let (az, el) = (azimuth_deg.to_radians(), elevation_deg.to_radians());
+        let lat = latitude_deg.to_radians();
+        let sin_dec = lat.sin() * el.sin() + lat.cos() * el.cos() * az.cos();
+        let hour_angle = f64::atan2(
+            -el.cos() * az.sin(),
+            lat.cos() * el.sin() - lat.sin() * el.cos() * az.cos(),
+        );
+        Self {
+            latitude: lat,
+            hour_angle,
+            declination: sin_dec.clamp(-1.0, 1.0).asin(),
+        }
+    }
+
+    /**
+     *  Project an ENU baseline (in metres) onto (u, v, w), in metres, by first
+     *  converting to the equatorial (X, Y, Z) frame.
+     */
+    pub fn project(&self, enu: [f64; 3]) -> [f64; 3] {
+        let [e, n, up] = enu;
+        let (sl, cl) = self.latitude.sin_cos();
+        let (sh, ch) = self.hour_angle.sin_cos();
+        let (sd, cd) = self.declination.sin_cos();
+
+        let x = -sl * n + cl * up;
+        let y = e;
+        let z = cl * n + sl * up;
+
+        [
+            sh * x + ch * y,
+            -sd * ch * x + sd * sh * y + cd * z,
+            cd * ch * x - cd * sh * y + sd * z,
+        ]
+    }
+}
+
+/**
+ * The baseline of each visibility output, in the output-order of a solution,
+ * along with the polarisation pair, for dual-polarisation.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub output: usize,
+    pub a: usize,
+    pub b: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pols: Option<(Polarisation, Polarisation)>,
+    pub enu: [f64; 3],
+    pub uvw: [f64; 3],
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BaselineMap {
+    pub frequency: f64,
+    pub pointing: Pointing,
+    pub baselines: Vec<Baseline>,
+}
+
+impl BaselineMap {
+    /**
+     *  Baselines, with (u, v, w) in wavelengths, for each visibility output of
+     *  the solution. Signal-means and idle outputs have no baselines.
+     */
+    pub fn new(
+        solution: &Solution,
+        array: &AntennaArray,
+        frequency: f64,
+        pointing: Pointing,
+    ) -> Result<Self, String> {
+        let n = solution.context.num_dishes();
+        if array.num_antennas() < n {
+            return Err(format!(
+                "Only {} antenna positions, for {} antennas",
+                array.num_antennas(),
+                n
+            ));
+        }
+        let wavelength = SPEED_OF_LIGHT / frequency;
+
+        let baselines = solution
+            .output_labels()
+            .iter()
+            .enumerate()
+            .filter_map(|(output, product)| match product {
+                Some(p) if !p.means => {
+                    let enu = array.baseline(p.a, p.b);
+                    let uvw = pointing.project(enu).map(|x| x / wavelength);
+                    Some(Baseline {
+                        output,
+                        a: p.a,
+                        b: p.b,
+                        pols: p.pols,
+                        enu,
+                        uvw,
+                    })
+                }
+                _ => None,
+            })
+            .collect();
+
+        Ok(Self {
+            frequency,
+            pointing,
+            baselines,
+        })
+    }
+
+    /**
+     *  Attach the baselines to the visibilities of a decoded frame.
+     */
+    pub fn visibilities(&self, words: &[(i64, i64)]) -> Vec<UvwVisibility> {
+        self.baselines
+            .iter()
+            .filter_map(|bl| {
+                words.get(bl.output).map(|&(re, im)| UvwVisibility {
+                    baseline: *bl,
+                    re,
+                    im,
+                })
+            })
+            .collect()
+    }
+}
+
+/**
+ * A visibility, with its baseline.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct UvwVisibility {
+    pub baseline: Baseline,
+    pub re: i64,
+    pub im: i64,
+}
+
+impl UvwVisibility {
+    pub const CSV_HEADER: &'static str = "output,a,b,u,v,w,re,im";
+
+    /**
+     *  Header for dual-polarisation, with the polarisation pair of each row.
+     */
+    pub const CSV_HEADER_POL: &'static str = "output,a,b,pol,u,v,w,re,im";
+
+    pub fn to_csv(&self) -> String {
+        let bl = &self.baseline;
+        let ab = match bl.pols {
+            Some((p, q)) => format!("{},{},{}{}", bl.a, bl.b, p, q),
+            None => format!("{},{}", bl.a, bl.b),
+        };
+        format!(
+            "{},{},{:.6},{:.6},{:.6},{},{}",
+            bl.output, ab, bl.uvw[0], bl.uvw[1], bl.uvw[2], self.re, self.im
+        )
+    }
+}
+
+impl fmt::Display for BaselineMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "BaselineMap {{")?;
+        writeln!(f, "    frequency: {} Hz", self.frequency)?;
+        writeln!(
+            f,
+            "    pointing: HA {:.3} deg, Dec {:.3} deg",
+            self.pointing.hour_angle.to_degrees(),
+            self.pointing.declination.to_degrees()
+        )?;
+        writeln!(f, "    baselines: {}", self.baselines.len())?;
+        writeln!(f, "}}")
+    }
+}
@@ -0,0 +1,199 @@
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/**
+ * Minimal (two's-complement) bit-widths for each of the accumulator stages of
+ * the correlator datapath, as determined by the ADC bit-width, the lengths of
+ * the partial-sum loops, and the number of partial-sums that are accumulated
+ * over each integration period.
+ *
+ * Stages:
+ *  - 'ABITS' for the first-stage adders (`correlate`, and `visaccum.IBITS`),
+ *    which sum 'LOOP0' products;
+ *  - 'SBITS' for the partial-sums (`visaccum.OBITS`, `visfinal.IBITS`), which
+ *    sum 'COUNT = LOOP0 * LOOP1' products; and
+ *  - 'ACCUM' for the final accumulators (`visfinal.OBITS`, `accumulator`),
+ *    which sum 'passes' partial-sums.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Eq)]
+pub struct BitWidths {
+    /* Integration settings */
+    pub adc_bits: usize,
+    pub loop0: usize,
+    pub loop1: usize,
+    pub passes: u64,
+
+    /* Derived bit-widths */
+    pub abits: usize,
+    pub sbits: usize,
+    pub accum: usize,
+    pub cbits: usize,
+}
+
+impl fmt::Display for BitWidths {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "// Bit-widths for {}-bit ADC data, with LOOP0 = {}, LOOP1 = {}, \
+             and {} partial-sums ({} samples) per integration",
+            self.adc_bits,
+            self.loop0,
+            self.loop1,
+            self.passes,
+            self.samples()
+        )?;
+        writeln!(f, "parameter integer LOOP0 = {};", self.loop0)?;
+        writeln!(f, "parameter integer LOOP1 = {};", self.loop1)?;
+        writeln!(f, "parameter integer ABITS = {};", self.abits)?;
+        writeln!(f, "parameter integer SBITS = {};", self.sbits)?;
+        writeln!(f, "parameter integer ACCUM = {};", self.accum)
+    }
+}
+
+/**
+ *  Number of bits required to store all values within '[-max, max]', using
+ *  two's-complement.
+ */
+pub fn signed_bits(max: u64) -> usize {
+    (u64::BITS - max.leading_zeros()) as usize + 1
+}
+
+/**
+ *  Largest magnitude of the (sign/magnitude) ADC values, for the given number
+ *  of ADC bits. The 2-bit values are '{-3, -1, 1, 3}', as per Table 16 of the
+ *  MAX2769 data sheet.
+ */
+pub fn adc_max_level(adc_bits: usize) -> Option<u64> {
+    match adc_bits {
+        1 => Some(1),
+        2 => Some(3),
+        _ => None,
+    }
+}
+
+impl BitWidths {
+    /**
+     *  Computes the minimal bit-widths for each accumulator stage, when each
+     *  visibility is formed from 'passes' partial-sums, of 'LOOP0 * LOOP1'
+     *  samples each.
+     */
+    pub fn new(
+        adc_bits: usize,
+        loop0: usize,
+        loop1: usize,
+        passes: u64,
+    ) -> Option<Self> {
+        let level = match adc_max_level(adc_bits) {
+            Some(level) => level,
+            None => {
+                error!("Only 1-bit and 2-bit ADC data is supported");
+                return None;
+            }
+        };
+        if loop0 == 0 || loop1 == 0 || passes == 0 {
+            error!("Loop-counts and partial-sum passes must be > 0");
+            return None;
+        }
+
+        // Each term of 'I_a * I_b + Q_a * Q_b' (and the imaginary component)
+        // is at most 'level^2'. And the signal-means terms, 'I_a + Q_a', are
+        // always smaller than this, so they do not affect the bit-widths.
+        let product = 2 * level * level;
+        let count = (loop0 * loop1) as u64;
+
+        let abits = signed_bits(product * loop0 as u64);
+        let sbits = signed_bits(product * count);
+        let accum = signed_bits((product * count).saturating_mul(passes));
+
+        // The 'accumulator' counts the partial-sums using 'count_i', which is
+        // 'ACCUM - SBITS + 1' bits wide.
+        let cbits = accum - sbits + 1;
+
+        Some(Self {
+            adc_bits,
+            loop0,
+            loop1,
+            passes,
+            abits,
+            sbits,
+            accum,
+            cbits,
+        })
+    }
+
+    /**
+     *  Computes the bit-widths for an integration-time, in seconds, given the
+     *  sampling-rate (in Hz) of the radios. The number of samples is rounded
+     *  up to a whole number of partial-sums.
+     */
+    pub fn from_time(
+        adc_bits: usize,
+        loop0: usize,
+        loop1: usize,
+        integration: f64,
+        sample_rate: f64,
+    ) -> Option<Self> {
+        let samples = (integration * sample_rate).ceil();
+        if !samples.is_finite() || samples < 1.0 {
+            error!("Integration-time must be at least one sample-period");
+            return None;
+        }
+        let count = (loop0 * loop1).max(1) as f64;
+        let passes = (samples / count).ceil() as u64;
+        Self::new(adc_bits, loop0, loop1, passes)
+    }
+
+    pub fn count(&self) -> usize {
+        self.loop0 * self.loop1
+    }
+
+    /**
+     *  Total number of samples that are correlated, per integration period.
+     */
+    pub fn samples(&self) -> u64 {
+        self.count() as u64 * self.passes
+    }
+
+    /**
+     *  Compare the given (hardware) bit-widths against the minimal widths, and
+     *  describe each stage that risks overflowing.
+     */
+    pub fn overflow_risks(
+        &self,
+        abits: Option<usize>,
+        sbits: Option<usize>,
+        accum: Option<usize>,
+    ) -> Vec<String> {
+        let mut risks = Vec::new();
+        let stages = [
+            ("ABITS", abits, self.abits),
+            ("SBITS", sbits, self.sbits),
+            ("ACCUM", accum, self.accum),
+        ];
+
+        for (name, given, least) in stages {
+            if let Some(bits) = given {
+                if bits < least {
+                    risks.push(format!(
+                        "{} = {} can overflow (requires at least {} bits)",
+                        name, bits, least
+                    ));
+                }
+            }
+        }
+
+        // Using a narrower 'ACCUM' also narrows the partial-sums counter
+        if let Some(bits) = accum {
+            let cbits = (bits + 1).saturating_sub(sbits.unwrap_or(self.sbits));
+            if cbits < 64 && self.passes >= 1 << cbits {
+                risks.push(format!(
+                    "accumulator 'count_i' ({} bits) can not count {} passes",
+                    cbits, self.passes
+                ));
+            }
+        }
+
+        risks
+    }
+}
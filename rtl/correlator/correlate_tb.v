@@ -70,6 +70,31 @@ module correlate_tb;
   end
 
 
+`ifdef __golden
+  // -- Golden input vectors, and expected partial-sums -- //
+
+  // Generated using 'cargo run -- --golden <DIR>', in 'bench/test_vectors'
+  localparam integer GLD_INPUT = TRATE * COUNT;
+  localparam integer GLD_SUMS = TRATE;
+
+  reg [4:0] gld_input[GLD_INPUT];
+  reg [2*WIDTH-1:0] gld_sums[GLD_SUMS];
+  reg gld_auto = 1'b0;
+  integer gld_index = 0;
+  integer gld_count = 0;
+  integer gld_errors = 0;
+
+  initial begin
+    $readmemh("correlate_tb_input.hex", gld_input);
+    $readmemh("correlate_tb_sums.hex", gld_sums);
+
+    #10 while (!cor_done) #10;
+    if (gld_errors == 0 && gld_count == GLD_SUMS) $display("PASSED");
+    else $display("FAILED: %0d errors, %0d/%0d checked", gld_errors, gld_count, GLD_SUMS);
+  end
+`endif
+
+
   // -- Generate fake data -- //
 
   reg src_valid, src_first, src_last;
@@ -112,8 +137,13 @@ module correlate_tb;
 
       if (src_frame) begin
         src_taddr <= taddr;
+`ifdef __golden
+        {gld_auto, src_qdata, src_idata} <= gld_input[gld_index];
+        gld_index <= gld_index + 1;
+`else
         src_idata <= $urandom;
         src_qdata <= $urandom;
+`endif
       end else begin
         src_valid <= 1'b0;
         src_auto  <= 1'b0;
@@ -147,6 +177,12 @@ module correlate_tb;
   wire bq = src_qdata[1];
   wire [MSB:0] cor_rdata, cor_idata;
 
+`ifdef __golden
+  wire cor_auto = gld_auto;
+`else
+  wire cor_auto = src_auto;
+`endif
+
   correlate #(
       .WIDTH(WIDTH)
   ) CORREL0 (
@@ -156,7 +192,7 @@ module correlate_tb;
       .valid_i(src_valid),
       .first_i(src_first),
       .last_i(src_last),
-      .auto_i(src_auto),
+      .auto_i(cor_auto),
       .ai_i(ai),
       .aq_i(aq),
       .bi_i(bi),
@@ -168,5 +204,19 @@ module correlate_tb;
       .idata_o(cor_idata)
   );
 
+`ifdef __golden
+  // Check the partial-sums against the expected values
+  always @(posedge clock) begin
+    if (!reset && cor_valid && gld_count < GLD_SUMS) begin
+      if ({cor_rdata, cor_idata} !== gld_sums[gld_count]) begin
+        $display("%10t: SUMS[%0d] = %h, expected %h", $time, gld_count,
+                 {cor_rdata, cor_idata}, gld_sums[gld_count]);
+        gld_errors <= gld_errors + 1;
+      end
+      gld_count <= gld_count + 1;
+    end
+  end
+`endif
+
 
 endmodule  // correlate_tb
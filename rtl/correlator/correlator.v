@@ -32,7 +32,7 @@ module correlator #(
     parameter unsigned [QSB:0] ASELS = {QBITS{1'bx}},
     parameter unsigned [QSB:0] BSELS = {QBITS{1'bx}},
 
-    parameter unsigned [TSB:0] AUTOS = {TBITS{1'bx}}
+    parameter unsigned [TRATE-1:0] AUTOS = {TRATE{1'bx}}
 ) (
     input clock,
     input reset,
@@ -80,13 +80,14 @@ module correlator #(
   end
 */
 
-  reg [TSB:0] autos;
+  // Per time-slot flags, for the auto-correlation (signal-means) slots
+  reg [TRATE-1:0] autos;
 
   always @(posedge clock) begin
     if (reset) begin
       autos <= AUTOS;
     end else begin
-      autos <= {1'bx, autos[TSB:1]};
+      autos <= {1'bx, autos[TRATE-1:1]};
     end
   end
 
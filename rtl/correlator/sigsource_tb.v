@@ -87,6 +87,30 @@ module sigsource_tb;
   end
 
 
+`ifdef __golden
+  // -- Golden input vectors, and expected MUX outputs -- //
+
+  // Generated using 'cargo run -- --golden <DIR>', in 'bench/test_vectors'
+  localparam integer GLD_INPUT = TRATE * COUNT;
+  localparam integer GLD_MUX = TRATE * COUNT;
+
+  reg [2*WIDTH:0] gld_input[GLD_INPUT];
+  reg [3:0] gld_mux[GLD_MUX];
+  integer gld_index = 0;
+  integer gld_count = 0;
+  integer gld_errors = 0;
+
+  initial begin
+    $readmemh("sigsource_tb_input.hex", gld_input);
+    $readmemh("sigsource_tb_mux.hex", gld_mux);
+
+    #10 while (!mux_done) #10;
+    if (gld_errors == 0 && gld_count == GLD_MUX) $display("PASSED");
+    else $display("FAILED: %0d errors, %0d/%0d checked", gld_errors, gld_count, GLD_MUX);
+  end
+`endif
+
+
   // -- Generate fake data -- //
 
   reg src_valid, src_first, src_last;
@@ -125,8 +149,13 @@ module sigsource_tb;
 
       if (src_frame) begin
         src_taddr <= taddr;
+`ifdef __golden
+        {src_qdata, src_idata} <= gld_input[gld_index][2*WIDTH-1:0];
+        gld_index <= gld_index + 1;
+`else
         src_idata <= $urandom;
         src_qdata <= $urandom;
+`endif
       end else begin
         src_valid <= 1'b0;
       end
@@ -184,4 +213,18 @@ module sigsource_tb;
       .bq_o(bq)
   );
 
+`ifdef __golden
+  // Check the MUX outputs against the expected values
+  always @(posedge clock) begin
+    if (!reset && mux_valid && gld_count < GLD_MUX) begin
+      if ({ai, aq, bi, bq} !== gld_mux[gld_count]) begin
+        $display("%10t: MUX[%0d] = %h, expected %h", $time, gld_count,
+                 {ai, aq, bi, bq}, gld_mux[gld_count]);
+        gld_errors <= gld_errors + 1;
+      end
+      gld_count <= gld_count + 1;
+    end
+  end
+`endif
+
 endmodule  // sigsource_tb
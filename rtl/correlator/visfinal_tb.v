@@ -38,6 +38,32 @@ module visfinal_tb;
   end
 
 
+`ifdef __golden
+  // -- Golden input partial-sums, and expected visibilities -- //
+
+  // Generated using 'cargo run -- --golden <DIR>', in 'bench/test_vectors'
+  localparam integer GLD_INPUT = NSUMS << SBITS;
+  localparam integer GLD_VIS = NSUMS;
+
+  reg [ISB:0] gld_input[GLD_INPUT];
+  reg [OSB:0] gld_vis[GLD_VIS];
+  integer gld_index = 0;
+  integer gld_count = 0;
+  integer gld_errors = 0;
+
+  initial begin
+    $readmemh("visfinal_tb_input.hex", gld_input);
+    $readmemh("visfinal_tb_vis.hex", gld_vis);
+
+    wait (!done);
+    wait (done);
+    #70;
+    if (gld_errors == 0 && gld_count == GLD_VIS) $display("PASSED");
+    else $display("FAILED: %0d errors, %0d/%0d checked", gld_errors, gld_count, GLD_VIS);
+  end
+`endif
+
+
   // -- Generate fake data -- //
 
   reg vld_r;
@@ -84,7 +110,12 @@ module visfinal_tb;
       lst_r <= nxt == {SBITS{1'b0}} && !done;
 
       if (go_w) begin
+`ifdef __golden
+        dat_r <= gld_index < GLD_INPUT ? gld_input[gld_index] : {IBITS{1'bx}};
+        gld_index <= gld_index + 1;
+`else
         dat_r <= $urandom;
+`endif
       end else begin
         dat_r <= {IBITS{1'bx}};
       end
@@ -117,4 +148,18 @@ module visfinal_tb;
       .data_o (dat_w)
   );
 
+`ifdef __golden
+  // Check the visibilities against the expected values
+  always @(posedge clock) begin
+    if (rst_n && vld_w && gld_count < GLD_VIS) begin
+      if (dat_w !== gld_vis[gld_count]) begin
+        $display("%10t: VIS[%0d] = %h, expected %h", $time, gld_count, dat_w,
+                 gld_vis[gld_count]);
+        gld_errors <= gld_errors + 1;
+      end
+      gld_count <= gld_count + 1;
+    end
+  end
+`endif
+
 endmodule  // visfinal_tb
@@ -18,9 +18,8 @@ module tart_24x_radio (
 
   // Correlator-source pairs, for each of the 12x time-slices, and for each of
   // the 24x correlators.
-  // Todo:
-  //  - write a script that converts these to 'xSELS', and 'xTAPS';
-  //  - figure out the required MUX-widths;
+  // Note: 'generator/src/bin/tart-pairs.rs' converts these to 'xSELS', and
+  //   'xTAPS', and computes the required MUX-widths.
   parameter PAIRS00_00 = {120'h5854148501384c15814048100380c0};
   parameter PAIRS00_01 = {120'h58d4348d0338cc35894248902388c2};
   parameter PAIRS00_02 = {120'h5954549505394c55914449104390c4};